@@ -0,0 +1,111 @@
+//! Timings for inputs that trigger the worst case of naive implementations
+//! of the algorithm. Each case is measured at increasing lengths; the time
+//! per character should stay roughly constant.
+//!
+//! Run with `cargo bench`.
+
+use bidi::state::State;
+use bidi::{reorder, Granularity, Level, Resolved};
+use std::time::{Duration, Instant};
+
+const LENGTHS: [usize; 3] = [10_000, 40_000, 160_000];
+
+fn main() {
+    // Unmatched first strong isolates, each scanning ahead for a strong type.
+    bench_text("unmatched FSI", |n| "\u{2068}".repeat(n));
+    // Nested first strong isolates, each closed at the end of the text.
+    bench_text("nested FSI", |n| {
+        let depth = n / 2;
+        "\u{2068}".repeat(depth) + &"\u{2069}".repeat(depth)
+    });
+    // Level runs ending with an isolate initiator that have no matching PDI
+    // in the same paragraph.
+    bench_text("unmatched isolate runs", |n| "a\u{2067}b\n".repeat(n / 4));
+    // Deeply nested embeddings producing many levels to reorder.
+    bench_text("nested embeddings", |n| {
+        let block = "\u{202B}\u{05D0}\u{202A}a".repeat(60) + &"\u{202C}".repeat(120);
+        block.repeat(n / (block.chars().count()))
+    });
+    // Brackets in many isolates, which the enclosing sequence has to skip
+    // while pairing its own brackets.
+    bench_text("brackets around isolates", |n| {
+        "(\u{05D0}\u{2066}[a]\u{2069}".repeat(n / 12) + &")".repeat(n / 12)
+    });
+    // Building the embedding tree also determines the direction of every
+    // first strong isolate.
+    bench_embeddings("embeddings of unmatched FSI", |n| "\u{2068}".repeat(n));
+    bench_reorder("reorder sawtooth", |i| {
+        let phase = (i % 250) as Level;
+        if phase < 125 {
+            phase
+        } else {
+            250 - phase
+        }
+    });
+    bench_reorder("reorder alternating", |i| if i & 1 == 0 { 1 } else { 125 });
+}
+
+fn bench_text(name: &str, make: impl Fn(usize) -> String) {
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    println!("{}", name);
+    for &n in &LENGTHS {
+        let text = make(n);
+        let len = text.chars().count();
+        let elapsed = measure(|| {
+            bidi::state::resolve_levels_into(
+                &mut state,
+                &text,
+                Granularity::Char,
+                None,
+                &mut resolved,
+            );
+        });
+        report(len, elapsed);
+    }
+}
+
+fn bench_embeddings(name: &str, make: impl Fn(usize) -> String) {
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    println!("{}", name);
+    for &n in &LENGTHS {
+        let text = make(n);
+        bidi::state::resolve_levels_into(&mut state, &text, Granularity::Char, None, &mut resolved);
+        let elapsed = measure(|| {
+            state.embeddings();
+        });
+        report(text.chars().count(), elapsed);
+    }
+}
+
+fn bench_reorder(name: &str, level: impl Fn(usize) -> Level) {
+    println!("{}", name);
+    for &n in &LENGTHS {
+        let levels = (0..n).map(&level).collect::<Vec<_>>();
+        let mut order = vec![0; n];
+        let elapsed = measure(|| reorder(&mut order, |i| levels[i]));
+        report(n, elapsed);
+    }
+}
+
+/// Returns the fastest of several runs.
+fn measure(mut f: impl FnMut()) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(len: usize, elapsed: Duration) {
+    println!(
+        "  {:>7} chars  {:>10.3} ms  {:>7.1} ns/char",
+        len,
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_nanos() as f64 / len as f64
+    );
+}
@@ -0,0 +1,241 @@
+//! Compact binary format for character data loaded at runtime.
+//!
+//! A blob consists of a header followed by three tables. All integers are
+//! little endian and code points are stored in three bytes.
+//!
+//! | Size | Content                                                    |
+//! |------|------------------------------------------------------------|
+//! | 4    | Magic bytes `BIDI`                                         |
+//! | 1    | Format version, currently 1                                |
+//! | 3    | Unicode version: major, minor and update                   |
+//! | 4    | Number of class ranges                                     |
+//! | 4    | Number of bracket pairs                                    |
+//! | 4    | Number of mirrored characters                              |
+//! | 7    | Per class range: first code point, end code point, type    |
+//! | 6    | Per bracket pair: opening bracket, closing bracket         |
+//! | 6    | Per mirrored character: code point, mirrored code point    |
+//!
+//! Class ranges are half open, ordered and disjoint. Characters outside of
+//! every range have a type of `L`. Bracket pairs are ordered by the opening
+//! bracket and mirrored characters by code point. Each bracket must be the
+//! mirrored glyph of the other bracket in its pair.
+
+use super::data::{BracketType, Provider, Type};
+
+const MAGIC: &[u8; 4] = b"BIDI";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 20;
+const CLASS_LEN: usize = 7;
+const PAIR_LEN: usize = 6;
+const TYPE_COUNT: u8 = 23;
+
+/// Character data in the compact binary format, validated on creation.
+///
+/// This can replace the tables compiled into the crate, either to update
+/// them without recompiling or to share them between binaries. See
+/// [`State::set_data`](crate::state::State::set_data).
+#[derive(Copy, Clone, Debug)]
+pub struct DataBlob<'a> {
+    version: (u8, u8, u8),
+    classes: &'a [u8],
+    brackets: &'a [u8],
+    mirroring: &'a [u8],
+}
+
+impl<'a> DataBlob<'a> {
+    /// Validates the specified bytes and creates a blob that borrows them.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DataError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC || bytes[4] != FORMAT_VERSION {
+            return Err(DataError::Header);
+        }
+        let count = |offset: usize| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[offset..offset + 4]);
+            u32::from_le_bytes(word) as usize
+        };
+        let classes_len = count(8).checked_mul(CLASS_LEN);
+        let brackets_len = count(12).checked_mul(PAIR_LEN);
+        let mirroring_len = count(16).checked_mul(PAIR_LEN);
+        let len = classes_len
+            .and_then(|len| len.checked_add(brackets_len?))
+            .and_then(|len| len.checked_add(mirroring_len?));
+        if len != Some(bytes.len() - HEADER_LEN) {
+            return Err(DataError::Length);
+        }
+        let (classes, rest) = bytes[HEADER_LEN..].split_at(classes_len.unwrap_or(0));
+        let (brackets, mirroring) = rest.split_at(brackets_len.unwrap_or(0));
+        let blob = Self {
+            version: (bytes[5], bytes[6], bytes[7]),
+            classes,
+            brackets,
+            mirroring,
+        };
+        let mut prev_end = 0;
+        for record in classes.chunks_exact(CLASS_LEN) {
+            let (start, end) = (u24(record), u24(&record[3..]));
+            if start < prev_end || start >= end || end > 0x110000 || record[6] >= TYPE_COUNT {
+                return Err(DataError::Classes);
+            }
+            prev_end = end;
+        }
+        if !is_ordered_map(mirroring) {
+            return Err(DataError::Mirroring);
+        }
+        if !is_ordered_map(brackets) {
+            return Err(DataError::Brackets);
+        }
+        for record in brackets.chunks_exact(PAIR_LEN) {
+            let (open, close) = (u24(record), u24(&record[3..]));
+            let mirror = |cp| blob.mirror(char::from_u32(cp)?).map(u32::from);
+            if mirror(open) != Some(close) || mirror(close) != Some(open) {
+                return Err(DataError::Brackets);
+            }
+        }
+        Ok(blob)
+    }
+
+    /// Returns the version of the Unicode Character Database that the data
+    /// was taken from.
+    pub fn unicode_version(&self) -> (u8, u8, u8) {
+        self.version
+    }
+
+    /// Returns the bidirectional type of the specified character.
+    pub fn bidi_class(&self, ch: char) -> Type {
+        match find(self.classes, CLASS_LEN, ch as u32, true) {
+            Some(record) => Type(record[6]),
+            None => Type::L,
+        }
+    }
+
+    /// Returns the character with the mirrored glyph of the specified
+    /// character, if any.
+    pub fn mirror(&self, ch: char) -> Option<char> {
+        let record = find(self.mirroring, PAIR_LEN, ch as u32, false)?;
+        char::from_u32(u24(&record[3..]))
+    }
+
+    /// Returns the paired bracket type of the specified character.
+    pub fn bracket_type(&self, ch: char) -> BracketType {
+        if let Some(close) = self.closing_bracket(ch) {
+            return BracketType::Open(close);
+        }
+        // As with the built in tables, the opening bracket is found through
+        // the mirrored glyph of the closing bracket.
+        match self.mirror(ch) {
+            Some(open) if self.closing_bracket(open) == Some(ch) => BracketType::Close(open),
+            _ => BracketType::None,
+        }
+    }
+
+    fn closing_bracket(&self, open: char) -> Option<char> {
+        let record = find(self.brackets, PAIR_LEN, open as u32, false)?;
+        char::from_u32(u24(&record[3..]))
+    }
+
+    /// Encodes the tables compiled into the crate in the binary format.
+    #[cfg(all(feature = "alloc", feature = "builtin-data"))]
+    pub fn encode_builtin() -> alloc::vec::Vec<u8> {
+        use super::data::{BIDI_CLASS_RANGES, BIDI_CLASS_VALUES, BRACKET_PAIRS, MIRRORING};
+        let mut bytes = alloc::vec::Vec::new();
+        let (major, minor, update) = super::UNICODE_VERSION;
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[FORMAT_VERSION, major, minor, update]);
+        for len in [
+            BIDI_CLASS_RANGES.len(),
+            BRACKET_PAIRS.len(),
+            MIRRORING.len(),
+        ] {
+            bytes.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+        for (&(start, end), ty) in BIDI_CLASS_RANGES.iter().zip(BIDI_CLASS_VALUES) {
+            bytes.extend_from_slice(&start.to_le_bytes()[..3]);
+            bytes.extend_from_slice(&end.to_le_bytes()[..3]);
+            bytes.push(ty.0);
+        }
+        for &(key, value) in BRACKET_PAIRS.iter().chain(MIRRORING) {
+            bytes.extend_from_slice(&key.to_le_bytes()[..3]);
+            bytes.extend_from_slice(&value.to_le_bytes()[..3]);
+        }
+        bytes
+    }
+}
+
+impl Provider for DataBlob<'_> {
+    fn bidi_class(&self, ch: char) -> Type {
+        DataBlob::bidi_class(self, ch)
+    }
+
+    fn bracket_type(&self, ch: char) -> BracketType {
+        DataBlob::bracket_type(self, ch)
+    }
+}
+
+/// Error returned when character data is not in the expected format.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DataError {
+    /// The magic bytes or the format version are not recognized.
+    Header,
+    /// The length does not match the table sizes given in the header.
+    Length,
+    /// The class ranges are unordered, overlapping, out of range or have
+    /// an unknown type.
+    Classes,
+    /// The bracket pairs are unordered, contain an invalid character or are
+    /// not mirrored glyphs of each other.
+    Brackets,
+    /// The mirrored characters are unordered or contain an invalid
+    /// character.
+    Mirroring,
+}
+
+impl core::fmt::Display for DataError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Header => "unrecognized bidi data header",
+            Self::Length => "bidi data length does not match its header",
+            Self::Classes => "invalid bidi class ranges",
+            Self::Brackets => "invalid bracket pairs",
+            Self::Mirroring => "invalid mirrored characters",
+        })
+    }
+}
+
+fn u24(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+/// Returns true if the records of a table that maps characters to
+/// characters are ordered by key and contain only valid characters.
+fn is_ordered_map(records: &[u8]) -> bool {
+    let mut prev = None;
+    for record in records.chunks_exact(PAIR_LEN) {
+        let (key, value) = (u24(record), u24(&record[3..]));
+        if char::from_u32(key).is_none() || char::from_u32(value).is_none() || prev >= Some(key) {
+            return false;
+        }
+        prev = Some(key);
+    }
+    true
+}
+
+/// Finds the record containing the code point in a table of records that
+/// begin with either a range or a single code point.
+fn find(records: &[u8], size: usize, cp: u32, ranged: bool) -> Option<&[u8]> {
+    let mut lo = 0;
+    let mut hi = records.len() / size;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let record = &records[mid * size..(mid + 1) * size];
+        let start = u24(record);
+        let end = if ranged { u24(&record[3..]) } else { start + 1 };
+        if cp < start {
+            hi = mid;
+        } else if cp >= end {
+            lo = mid + 1;
+        } else {
+            return Some(record);
+        }
+    }
+    None
+}
@@ -0,0 +1,245 @@
+//! Encoding levels as text with directional formatting characters.
+
+use super::state::Options;
+use super::visual::{is_strong, run_mark, LRM, RLM};
+use super::{is_removed_by_x9, resolve_levels, Granularity, Level, LevelRange, Type};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const LRO: char = '\u{202D}';
+const RLO: char = '\u{202E}';
+const PDF: char = '\u{202C}';
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const PDI: char = '\u{2069}';
+
+/// Returns a copy of the text with the directional formatting characters
+/// needed so that resolving it with the specified base level gives each
+/// original character the corresponding level.
+///
+/// This is a heuristic that returns the first of a few candidates that
+/// reproduces the levels, tried in order of increasing intrusiveness, and
+/// does not search for the shortest encoding. The candidates are the text
+/// itself, marks at the boundaries of level runs, isolates with overrides
+/// for runs whose characters would not otherwise resolve to the requested
+/// level, the same with a strong mark after runs that end in whitespace so
+/// that rule L1 does not reset it, and finally isolates with marks at the
+/// boundaries of every run. So no characters are inserted if the text
+/// already resolves to the levels. Otherwise, if marks in the direction of
+/// each run are enough, there is at most one on each side of a level run
+/// and only next to a character that is not strong in that direction.
+///
+/// Returns `None` if the number of levels does not match the number of
+/// characters or none of the candidates reproduces the levels. This is
+/// always the case for levels the algorithm cannot produce, for example a
+/// segment separator above the base level (L1), but as the search is not
+/// exhaustive, it may also happen for levels that another encoding would
+/// produce. The requested levels of characters removed by rule X9 are
+/// ignored.
+pub fn encode_levels(text: &str, levels: &[Level], base_level: Level) -> Option<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    if chars.len() != levels.len() {
+        return None;
+    }
+    if levels
+        .iter()
+        .any(|&level| level < base_level || level > super::MAX_STACK as Level)
+    {
+        return None;
+    }
+    let runs = level_runs(levels);
+    [
+        Strategy::Plain,
+        Strategy::Marks,
+        Strategy::Isolates,
+        Strategy::IsolatesAndTrailingMarks,
+        Strategy::IsolatesAndMarks,
+    ]
+    .iter()
+    .find_map(|strategy| {
+        let mut encoder = Encoder {
+            data: Options::default(),
+            chars: &chars,
+            runs: &runs,
+            base_level,
+            text: String::new(),
+            original: Vec::new(),
+        };
+        match strategy {
+            Strategy::Plain => encoder.push_run(0..chars.len()),
+            Strategy::Marks => encoder.marks(),
+            Strategy::Isolates => encoder.isolates(false, false),
+            Strategy::IsolatesAndTrailingMarks => encoder.isolates(false, true),
+            Strategy::IsolatesAndMarks => encoder.isolates(true, true),
+        }
+        if encoder.verify(levels) {
+            Some(encoder.text)
+        } else {
+            None
+        }
+    })
+}
+
+/// Ways of encoding levels, in order of increasing intrusiveness.
+enum Strategy {
+    Plain,
+    Marks,
+    Isolates,
+    IsolatesAndTrailingMarks,
+    IsolatesAndMarks,
+}
+
+fn level_runs(levels: &[Level]) -> Vec<LevelRange> {
+    let mut runs: Vec<LevelRange> = Vec::new();
+    for (i, &level) in levels.iter().enumerate() {
+        match runs.last_mut() {
+            Some((last, range)) if *last == level => range.end = i + 1,
+            _ => runs.push((level, i..i + 1)),
+        }
+    }
+    runs
+}
+
+struct Encoder<'a> {
+    /// Options that select the character data.
+    data: Options<'a>,
+    chars: &'a [char],
+    runs: &'a [LevelRange],
+    base_level: Level,
+    text: String,
+    /// True for each character of the output that was in the input.
+    original: Vec<bool>,
+}
+
+impl Encoder<'_> {
+    fn push(&mut self, ch: char, original: bool) {
+        self.text.push(ch);
+        self.original.push(original);
+    }
+
+    fn push_run(&mut self, range: core::ops::Range<usize>) {
+        for &ch in &self.chars[range] {
+            self.push(ch, true);
+        }
+    }
+
+    /// Returns true if a mark is needed next to the character at the
+    /// specified boundary of a run. The edges of the text at the base level
+    /// are already in the direction of the base level.
+    fn needs_mark(&self, index: usize, mark: char, at_edge: bool) -> bool {
+        !at_edge && !is_strong(self.data, Some(self.chars[index]), mark)
+    }
+
+    /// Surrounds each run with marks in its direction where the characters
+    /// at its boundaries are not already strong in that direction.
+    fn marks(&mut self) {
+        let len = self.chars.len();
+        for (level, range) in self.runs {
+            let mark = run_mark(*level, self.base_level);
+            let at_base = *level == self.base_level;
+            if self.needs_mark(range.start, mark, at_base && range.start == 0) {
+                self.push(mark, false);
+            }
+            self.push_run(range.clone());
+            if self.needs_mark(range.end - 1, mark, at_base && range.end == len) {
+                self.push(mark, false);
+            }
+        }
+    }
+
+    /// Returns true if the run ends in whitespace, which rule L1 resets to
+    /// the base level when it precedes a separator or the end of the text.
+    fn ends_in_whitespace(&self, range: &core::ops::Range<usize>) -> bool {
+        let t = self.data.char_class(self.chars[range.end - 1]);
+        t.is_resettable() || is_removed_by_x9(t)
+    }
+
+    /// Places each run above the base level in nested isolates, adding an
+    /// override when its characters would not resolve to the level of the
+    /// isolate by themselves. Optionally surrounds the other runs with marks
+    /// in their direction, or only follows runs that end in whitespace with
+    /// a mark so that it is not reset.
+    fn isolates(&mut self, marks: bool, trailing: bool) {
+        let base = self.base_level;
+        let len = self.chars.len();
+        // Embedding levels of the open isolates.
+        let mut stack = alloc::vec![base];
+        for (level, range) in self.runs {
+            let level = *level;
+            let top = |stack: &Vec<Level>| *stack.last().unwrap();
+            let natural = level == base
+                || self.chars[range.clone()]
+                    .iter()
+                    .all(|&ch| resolves_to(self.data.char_class(ch), level));
+            // The level of the innermost isolate: the run itself or the level
+            // from which a single override reaches it.
+            let target = if natural {
+                level
+            } else {
+                level.saturating_sub(2).max(base)
+            };
+            while top(&stack) > level || (!natural && top(&stack) == level) {
+                stack.pop();
+                self.push(PDI, false);
+            }
+            while top(&stack) < target {
+                let cur = top(&stack);
+                let next = if target - cur >= 2 { cur + 2 } else { cur + 1 };
+                self.push(if next & 1 != 0 { RLI } else { LRI }, false);
+                stack.push(next);
+            }
+            if natural {
+                let mark = if level & 1 == 0 { LRM } else { RLM };
+                let at_base = level == base;
+                if marks && self.needs_mark(range.start, mark, at_base && range.start == 0) {
+                    self.push(mark, false);
+                }
+                self.push_run(range.clone());
+                let at_edge = at_base && range.end == len;
+                if (marks && self.needs_mark(range.end - 1, mark, at_edge))
+                    || (trailing && !at_base && self.ends_in_whitespace(range))
+                {
+                    self.push(mark, false);
+                }
+            } else {
+                self.push(if level & 1 != 0 { RLO } else { LRO }, false);
+                self.push_run(range.clone());
+                if trailing && self.ends_in_whitespace(range) {
+                    self.push(if level & 1 != 0 { RLM } else { LRM }, false);
+                }
+                self.push(PDF, false);
+            }
+        }
+        while stack.len() > 1 {
+            stack.pop();
+            self.push(PDI, false);
+        }
+    }
+
+    /// Returns true if resolving the output reproduces the levels.
+    fn verify(&self, levels: &[Level]) -> bool {
+        let resolved = resolve_levels(&self.text, Granularity::Char, Some(self.base_level));
+        let mut expected = levels.iter().zip(self.chars);
+        resolved
+            .levels
+            .iter()
+            .zip(&self.original)
+            .filter(|(_, original)| **original)
+            .all(|(level, _)| match expected.next() {
+                Some((expected, ch)) => {
+                    is_removed_by_x9(self.data.char_class(*ch)) || level == expected
+                }
+                None => false,
+            })
+    }
+}
+
+/// Returns true if a character of the specified type resolves to the level
+/// of the isolate that contains it when surrounded by neutral context.
+fn resolves_to(ty: Type, level: Level) -> bool {
+    if level & 1 != 0 {
+        !matches!(ty, Type::L | Type::EN | Type::AN)
+    } else {
+        !matches!(ty, Type::R | Type::AL | Type::AN)
+    }
+}
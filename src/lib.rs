@@ -2,15 +2,33 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+mod blob;
 mod data;
+#[cfg(feature = "alloc")]
+mod encode;
+#[cfg(feature = "alloc")]
+pub mod structured;
+mod trace;
+#[cfg(feature = "alloc")]
+mod visual;
 
-pub use data::Type;
+pub use blob::{DataBlob, DataError};
+#[cfg(feature = "builtin-data")]
+pub use data::{mirror, BuiltinProvider, UNICODE_VERSION};
+pub use data::{BracketType, Provider, Type};
+#[cfg(feature = "alloc")]
+pub use encode::encode_levels;
+#[cfg(feature = "alloc")]
+pub use visual::{write_logical, write_reordered, InverseMode, WriteOptions};
 
 use bidi_classes::*;
 use data::*;
+use trace::Tracer;
 
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 use core::ops::Range;
 
@@ -25,19 +43,27 @@ pub mod state {
     use super::*;
 
     /// Bidirectional algorithm state; reusable to avoid repeated allocations.
+    ///
+    /// The functions in this module that take text skip resolution when
+    /// [`requires_bidi`] rules out anything but level 0 and no other base
+    /// level is requested. The state is then cleared, so the accessors
+    /// describe an empty paragraph.
+    #[cfg(feature = "alloc")]
     #[derive(Clone, Default)]
-    pub struct State {
+    pub struct State<'a> {
         base_level: u8,
         initial_classes: Vec<Type>,
         types: Vec<Type>,
-        brackets: Vec<(usize, char, BracketType)>,
-        bracket_pairs: Vec<(usize, usize)>,
+        brackets: Vec<(usize, Bracket)>,
+        bracket_pairs: Vec<BracketPair>,
         runs: Vec<Run>,
         indices: Vec<usize>,
         flags: u16,
+        options: Options<'a>,
     }
 
-    impl State {
+    #[cfg(feature = "alloc")]
+    impl<'a> State<'a> {
         /// Creates a new resolver.
         pub fn new() -> Self {
             Self {
@@ -49,6 +75,7 @@ pub mod state {
                 runs: Vec::new(),
                 indices: Vec::new(),
                 flags: 0,
+                options: Options::DEFAULT,
             }
         }
 
@@ -58,10 +85,133 @@ pub mod state {
             self.types.clear();
             self.brackets.clear();
             self.bracket_pairs.clear();
+            self.runs.clear();
             self.flags = 0;
             self.base_level = 0;
         }
 
+        /// Sets whether characters removed by rule X9 are retained as
+        /// described in section 5.2 of UAX #9.
+        ///
+        /// If not, removed characters receive the level of the preceding
+        /// character, or the base level if there is none, so that they can be
+        /// displayed. When retained, embedding and override initiators and
+        /// PDFs receive the level of the embedding outside of them and other
+        /// removed characters the level of their embedding. Those adjacent to
+        /// numbers, separators or neutrals at the same level are then
+        /// resolved along with them. Rule L1 also resets them to the base
+        /// level along with adjacent whitespace preceding a segment
+        /// separator, paragraph separator or the end of the line. The levels
+        /// of the other characters are the same either way. Defaults to
+        /// false.
+        pub fn set_retain_removed(&mut self, retain: bool) {
+            self.options.retain_removed = retain;
+        }
+
+        /// Sets the variant of the algorithm used to resolve levels and to
+        /// reorder lines. Defaults to [`ReorderingMode::Default`].
+        pub fn set_reordering_mode(&mut self, mode: ReorderingMode) {
+            self.options.mode = mode;
+        }
+
+        /// Sets the version of the algorithm used to resolve levels. Defaults
+        /// to [`AlgorithmVersion::Current`].
+        pub fn set_algorithm_version(&mut self, version: AlgorithmVersion) {
+            self.options.version = version;
+        }
+
+        /// Sets the character data used instead of the tables compiled into
+        /// the crate, or `None` to use the built in tables. Defaults to
+        /// `None`.
+        ///
+        /// The data provides the paired brackets for rule N0 and, for the
+        /// functions in this module that take text, the bidirectional types.
+        /// The state borrows the data, so data loaded at runtime can be freed
+        /// once the state is dropped. Without the `builtin-data`
+        /// feature there are no built in tables, and every character is
+        /// treated as left to right until data or a provider is set.
+        pub fn set_data(&mut self, data: Option<DataBlob<'a>>) {
+            self.options.source = data.map_or(Source::Builtin, Source::Data);
+        }
+
+        /// Sets the provider of character properties used instead of the
+        /// tables compiled into the crate, or `None` to use the built in
+        /// tables. This replaces any data set by [`State::set_data`].
+        ///
+        /// As with character data, the provider supplies the paired brackets
+        /// for rule N0 and, for the functions in this module that take text,
+        /// the bidirectional types.
+        pub fn set_provider(&mut self, provider: Option<&'a (dyn Provider + Sync)>) {
+            self.options.source = provider.map_or(Source::Builtin, Source::Provider);
+        }
+
+        /// Returns the options, which also select the character data.
+        pub(crate) fn options(&self) -> Options<'a> {
+            self.options
+        }
+
+        /// Computes an ordering for a line of the most recently resolved
+        /// paragraph according to the reordering mode.
+        ///
+        /// This is equivalent to [`reorder`] unless the mode is
+        /// [`ReorderingMode::RunsOnly`].
+        pub fn reorder(&self, order: &mut [usize], levels: impl Fn(usize) -> Level) {
+            reorder_line(self.options.mode, self.base_level, order, levels);
+        }
+
+        /// Returns an iterator yielding true for each character in the most
+        /// recently resolved paragraph that was removed by rule X9.
+        pub fn removed(&self) -> impl Iterator<Item = bool> + '_ {
+            removed(&self.initial_classes)
+        }
+
+        /// Returns an iterator yielding the resolved bidirectional type of
+        /// each character in the most recently resolved paragraph.
+        ///
+        /// After rules N1 and N2, every character has a type of `L` or `R`,
+        /// except for numbers that remained `EN` or `AN`. Characters removed
+        /// by rule X9 retain their original type.
+        pub fn resolved_types(&self) -> impl Iterator<Item = Type> + '_ {
+            let len = self.initial_classes.len();
+            let final_types = if self.flags & 1 != 0 {
+                &[]
+            } else {
+                &self.types[len..len * 2]
+            };
+            resolved_types(&self.initial_classes, final_types)
+        }
+
+        /// Returns the bracket pairs (BD16) that were matched by rule N0 in
+        /// the most recently resolved paragraph, ordered by the position of
+        /// the opening bracket.
+        ///
+        /// Paragraphs at level 0 that contain no right-to-left characters or
+        /// explicit formatting characters are resolved without applying the
+        /// implicit rules, so no pairs are reported for them.
+        pub fn bracket_pairs(&self) -> &[BracketPair] {
+            &self.bracket_pairs
+        }
+
+        /// Returns the explicit embeddings, overrides and isolates of the
+        /// most recently resolved paragraph as a tree, in logical order.
+        ///
+        /// The tree follows the directional status stack of rules X1-X8.
+        /// Initiators that exceed the maximum depth and closing characters
+        /// that do not match an initiator have no effect on the stack, so
+        /// they do not appear in the tree.
+        pub fn embeddings(&self) -> Vec<Embedding> {
+            embeddings(self.options, &self.initial_classes, self.base_level)
+        }
+
+        /// Returns an iterator over the isolating run sequences (BD13) of the
+        /// most recently resolved paragraph.
+        pub fn sequences(&self) -> Sequences<'_> {
+            Sequences {
+                runs: &self.runs,
+                pos: 0,
+            }
+        }
+
         /// Resolves a paragraph specified by an iterator yielding characters
         /// and their associated bidirectional classes.
         pub fn resolve(
@@ -70,337 +220,1146 @@ pub mod state {
             base_level: Option<Level>,
             resolved: &mut Resolved<Level>,
         ) {
-            self.clear();
+            let items = char_items(self.options, chars);
+            self.resolve_impl(items, base_level, resolved, &mut Tracer::none());
+        }
+
+        /// Resolves a paragraph specified by an iterator yielding the
+        /// bidirectional class and paired bracket, if any, of each item
+        /// instead of characters.
+        ///
+        /// This allows resolving glyph clusters or objects that are not text,
+        /// such as inline images, along with text. The types and brackets
+        /// are used as given, without consulting any character data.
+        pub fn resolve_classes(
+            &mut self,
+            items: impl Iterator<Item = (Type, Option<Bracket>)>,
+            base_level: Option<Level>,
+            resolved: &mut Resolved<Level>,
+        ) {
+            let items = items.map(|(t, bracket)| (None, t, bracket));
+            self.resolve_impl(items, base_level, resolved, &mut Tracer::none());
+        }
+
+        /// Resolves a span of a paragraph specified by iterators yielding the
+        /// characters that precede the span, the characters of the span and
+        /// the characters that follow it, along with their bidirectional
+        /// classes (HL5).
+        ///
+        /// The whole paragraph is resolved, so the context determines the
+        /// embedding and isolate state, sos and eos, and the resolution of
+        /// weak and neutral types at the edges of the span, but `resolved`
+        /// receives only the levels of the span. The other accessors of the
+        /// state describe the whole paragraph, in which the span starts at
+        /// the number of characters in the prefix.
+        pub fn resolve_with_context(
+            &mut self,
+            prefix: impl Iterator<Item = (char, Type)>,
+            chars: impl Iterator<Item = (char, Type)>,
+            suffix: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            resolved: &mut Resolved<Level>,
+        ) {
+            let mut start = 0;
+            let mut len = 0;
+            let chars = prefix
+                .inspect(|_| start += 1)
+                .chain(chars.inspect(|_| len += 1))
+                .chain(suffix);
+            self.resolve(chars, base_level, resolved);
+            resolved.levels.truncate(start + len);
+            resolved.levels.drain(..start);
+        }
+
+        /// Resolves a paragraph like [`resolve`](Self::resolve) and writes a
+        /// trace of the intermediate state after each phase of the algorithm
+        /// to the specified target.
+        ///
+        /// The trace records the bidirectional types of every character in
+        /// each isolating run sequence, along with the sos and eos types and
+        /// the embedding levels. The layout follows the debug output of the
+        /// UAX #9 reference implementation.
+        pub fn resolve_traced(
+            &mut self,
+            chars: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            resolved: &mut Resolved<Level>,
+            trace: &mut dyn core::fmt::Write,
+        ) -> core::fmt::Result {
+            let mut tracer = Tracer::new(trace);
+            let items = char_items(self.options, chars);
+            self.resolve_impl(items, base_level, resolved, &mut tracer);
+            tracer.finish()
+        }
+
+        /// Resolves a paragraph like [`resolve`](Self::resolve) and reports
+        /// conditions that may indicate malformed text to the specified
+        /// callback, in the order they are encountered.
+        ///
+        /// Diagnostics from the explicit rules are reported in logical
+        /// order, followed by bracket stack overflows for each isolating run
+        /// sequence.
+        pub fn resolve_with_diagnostics(
+            &mut self,
+            chars: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            resolved: &mut Resolved<Level>,
+            diagnostics: &mut dyn FnMut(Diagnostic),
+        ) {
+            let items = char_items(self.options, chars);
+            let mut tracer = Tracer::diagnostics(diagnostics);
+            self.resolve_impl(items, base_level, resolved, &mut tracer);
+        }
+
+        fn resolve_impl(
+            &mut self,
+            items: impl Iterator<Item = Item>,
+            base_level: Option<Level>,
+            resolved: &mut Resolved<Level>,
+            tracer: &mut Tracer,
+        ) {
+            self.resolve_paragraph(items, base_level, tracer);
             resolved.granularity = Granularity::Char;
-            resolved.base_level = 0;
+            resolved.base_level = self.base_level;
             resolved.levels.clear();
-            let mut needs_bidi = false;
-            let mut len = 0;
-            for (i, (ch, t)) in chars.enumerate() {
+            resolved.levels.extend(self.levels());
+        }
+
+        /// Resolves a paragraph into the buffers of the state, from which
+        /// the levels are derived by [`levels`](Self::levels).
+        fn resolve_paragraph(
+            &mut self,
+            items: impl Iterator<Item = Item>,
+            base_level: Option<Level>,
+            tracer: &mut Tracer,
+        ) {
+            self.clear();
+            let mut input = Input::default();
+            tracer.header("Input", "P1");
+            tracer.begin_text();
+            for (i, (ch, t, bracket)) in items.enumerate() {
+                let t = self.options.class(t);
                 self.initial_classes.push(t);
-                let bracket = BracketType::from_char(ch);
-                if bracket != BracketType::None {
-                    self.brackets.push((i, ch, bracket));
+                if let Some(bracket) = self.options.bracket(bracket) {
+                    self.brackets.push((i, bracket));
+                }
+                input.add(t);
+                if let Some(ch) = ch {
+                    tracer.text(ch);
                 }
-                needs_bidi = needs_bidi || t.mask() & BIDI_MASK != 0;
-                len += 1;
             }
-            self.base_level =
-                base_level.unwrap_or_else(|| Self::default_level(&self.initial_classes));
-            resolved.base_level = self.base_level;
-            if !needs_bidi && self.base_level == 0 {
+            tracer.end_row();
+            tracer.types("Bidi_Class:", &self.initial_classes);
+            let len = self.initial_classes.len();
+            self.base_level = input.base_level(&self.initial_classes, base_level, tracer);
+            if input.is_simple(self.base_level) {
                 self.flags |= 1;
-                resolved.levels.resize(len, self.base_level);
+                self.runs.extend(simple_run(&self.initial_classes));
+                tracer.header("Resolved levels", "L1");
+                tracer.levels(&self.initial_classes, self.levels());
                 return;
             }
             self.types.extend_from_slice(&self.initial_classes);
-            self.resolve_levels(resolved);
-            self.resolve_runs(resolved);
-            for i in 0..self.runs.len() {
-                if self.runs[i].in_sequence {
-                    continue;
+            self.types.extend_from_slice(&self.initial_classes);
+            self.types.resize(len * 3, ON);
+            self.indices.resize(len, 0);
+            self.runs.resize(input.max_runs(), Run::EMPTY);
+            self.bracket_pairs
+                .resize(self.brackets.len() / 2, BracketPair::EMPTY);
+            let (types, rest) = self.types.split_at_mut(len);
+            let (final_types, seq_types) = rest.split_at_mut(len);
+            let mut paragraph = Paragraph {
+                options: self.options,
+                base_level: self.base_level,
+                initial_classes: &self.initial_classes,
+                brackets: &self.brackets,
+                types,
+                final_types,
+                seq_types,
+                indices: &mut self.indices,
+                runs: &mut self.runs,
+                run_count: 0,
+                bracket_pairs: &mut self.bracket_pairs,
+                pair_count: 0,
+            };
+            paragraph.resolve(tracer);
+            let (run_count, pair_count) = (paragraph.run_count, paragraph.pair_count);
+            self.runs.truncate(run_count);
+            self.bracket_pairs.truncate(pair_count);
+        }
+
+        /// Returns an iterator over the levels of the most recently resolved
+        /// paragraph, derived from the level runs and the resolved types.
+        fn levels(&self) -> Levels<'_> {
+            let len = self.initial_classes.len();
+            let (types, final_types) = if self.flags & 1 != 0 {
+                (&[][..], &[][..])
+            } else {
+                self.types[..len * 2].split_at(len)
+            };
+            Levels::new(
+                self.options,
+                self.base_level,
+                &self.initial_classes,
+                types,
+                final_types,
+                &self.runs,
+                &self.indices,
+            )
+        }
+    }
+
+    /// Bidirectional algorithm state with fixed capacity storage that
+    /// resolves paragraphs of up to `N` characters without allocating.
+    ///
+    /// The state is large, so it is typically kept in a `static` or other
+    /// long lived storage and reused.
+    #[derive(Clone)]
+    pub struct FixedState<'a, const N: usize> {
+        base_level: u8,
+        len: usize,
+        initial_classes: [Type; N],
+        types: [Type; N],
+        final_types: [Type; N],
+        seq_types: [Type; N],
+        brackets: [(usize, Bracket); N],
+        bracket_count: usize,
+        bracket_pairs: [BracketPair; N],
+        pair_count: usize,
+        runs: [Run; N],
+        run_count: usize,
+        indices: [usize; N],
+        flags: u16,
+        options: Options<'a>,
+    }
+
+    impl<'a, const N: usize> FixedState<'a, N> {
+        /// Creates a new resolver.
+        pub const fn new() -> Self {
+            Self {
+                base_level: 0,
+                len: 0,
+                initial_classes: [ON; N],
+                types: [ON; N],
+                final_types: [ON; N],
+                seq_types: [ON; N],
+                brackets: [(0, Bracket::Open(0)); N],
+                bracket_count: 0,
+                bracket_pairs: [BracketPair::EMPTY; N],
+                pair_count: 0,
+                runs: [Run::EMPTY; N],
+                run_count: 0,
+                indices: [0; N],
+                flags: 0,
+                options: Options::DEFAULT,
+            }
+        }
+
+        /// Returns the maximum number of characters in a paragraph.
+        pub const fn capacity(&self) -> usize {
+            N
+        }
+
+        /// Sets whether characters removed by rule X9 are retained as
+        /// described in section 5.2 of UAX #9.
+        ///
+        /// See [`State::set_retain_removed`] for details.
+        pub fn set_retain_removed(&mut self, retain: bool) {
+            self.options.retain_removed = retain;
+        }
+
+        /// Sets the variant of the algorithm used to resolve levels and to
+        /// reorder lines.
+        ///
+        /// See [`State::set_reordering_mode`] for details.
+        pub fn set_reordering_mode(&mut self, mode: ReorderingMode) {
+            self.options.mode = mode;
+        }
+
+        /// Sets the version of the algorithm used to resolve levels.
+        ///
+        /// See [`State::set_algorithm_version`] for details.
+        pub fn set_algorithm_version(&mut self, version: AlgorithmVersion) {
+            self.options.version = version;
+        }
+
+        /// Sets the character data that provides the paired brackets for
+        /// rule N0 instead of the tables compiled into the crate, or `None` to
+        /// use the built in tables.
+        pub fn set_data(&mut self, data: Option<DataBlob<'a>>) {
+            self.options.source = data.map_or(Source::Builtin, Source::Data);
+        }
+
+        /// Sets the provider that supplies the paired brackets for rule N0
+        /// instead of the tables compiled into the crate, or `None` to use
+        /// the built in tables. This replaces any data set by
+        /// [`FixedState::set_data`].
+        pub fn set_provider(&mut self, provider: Option<&'a (dyn Provider + Sync)>) {
+            self.options.source = provider.map_or(Source::Builtin, Source::Provider);
+        }
+
+        /// Computes an ordering for a line of the most recently resolved
+        /// paragraph according to the reordering mode.
+        ///
+        /// See [`State::reorder`] for details.
+        pub fn reorder(&self, order: &mut [usize], levels: impl Fn(usize) -> Level) {
+            reorder_line(self.options.mode, self.base_level, order, levels);
+        }
+
+        /// Returns an iterator yielding true for each character in the most
+        /// recently resolved paragraph that was removed by rule X9.
+        pub fn removed(&self) -> impl Iterator<Item = bool> + '_ {
+            removed(&self.initial_classes[..self.len])
+        }
+
+        /// Returns an iterator yielding the resolved bidirectional type of
+        /// each character in the most recently resolved paragraph.
+        ///
+        /// See [`State::resolved_types`] for details.
+        pub fn resolved_types(&self) -> impl Iterator<Item = Type> + '_ {
+            let final_types = if self.flags & 1 != 0 {
+                &[]
+            } else {
+                &self.final_types[..self.len]
+            };
+            resolved_types(&self.initial_classes[..self.len], final_types)
+        }
+
+        /// Returns the bracket pairs (BD16) that were matched by rule N0 in
+        /// the most recently resolved paragraph.
+        ///
+        /// See [`State::bracket_pairs`] for details.
+        pub fn bracket_pairs(&self) -> &[BracketPair] {
+            &self.bracket_pairs[..self.pair_count]
+        }
+
+        /// Returns the explicit embeddings, overrides and isolates of the
+        /// most recently resolved paragraph as a tree.
+        ///
+        /// See [`State::embeddings`] for details.
+        #[cfg(feature = "alloc")]
+        pub fn embeddings(&self) -> Vec<Embedding> {
+            embeddings(
+                self.options,
+                &self.initial_classes[..self.len],
+                self.base_level,
+            )
+        }
+
+        /// Returns an iterator over the isolating run sequences (BD13) of the
+        /// most recently resolved paragraph.
+        pub fn sequences(&self) -> Sequences<'_> {
+            Sequences {
+                runs: &self.runs[..self.run_count],
+                pos: 0,
+            }
+        }
+
+        /// Resolves a paragraph specified by an iterator yielding characters
+        /// and their associated bidirectional classes, storing the level of
+        /// each character in the corresponding element of `levels`.
+        ///
+        /// Returns the base level of the paragraph, which is detected if not
+        /// provided. Fails without resolving if the paragraph contains more
+        /// than `N` characters or does not fit in `levels`.
+        pub fn resolve(
+            &mut self,
+            chars: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            levels: &mut [Level],
+        ) -> Result<Level, CapacityError> {
+            let items = char_items(self.options, chars);
+            self.resolve_impl(items, base_level, levels, &mut Tracer::none())
+        }
+
+        /// Resolves a paragraph specified by an iterator yielding the
+        /// bidirectional class and paired bracket, if any, of each item
+        /// instead of characters, storing the level of each item in the
+        /// corresponding element of `levels`.
+        ///
+        /// See [`State::resolve_classes`] for details.
+        pub fn resolve_classes(
+            &mut self,
+            items: impl Iterator<Item = (Type, Option<Bracket>)>,
+            base_level: Option<Level>,
+            levels: &mut [Level],
+        ) -> Result<Level, CapacityError> {
+            let items = items.map(|(t, bracket)| (None, t, bracket));
+            self.resolve_impl(items, base_level, levels, &mut Tracer::none())
+        }
+
+        /// Resolves a paragraph like [`resolve`](Self::resolve) and writes a
+        /// trace of the intermediate state after each phase of the algorithm
+        /// to the specified target.
+        ///
+        /// See [`State::resolve_traced`] for details. Errors reported by the
+        /// target are ignored.
+        pub fn resolve_traced(
+            &mut self,
+            chars: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            levels: &mut [Level],
+            trace: &mut dyn core::fmt::Write,
+        ) -> Result<Level, CapacityError> {
+            let items = char_items(self.options, chars);
+            self.resolve_impl(items, base_level, levels, &mut Tracer::new(trace))
+        }
+
+        /// Resolves a paragraph like [`resolve`](Self::resolve) and reports
+        /// conditions that may indicate malformed text to the specified
+        /// callback.
+        ///
+        /// See [`State::resolve_with_diagnostics`] for details.
+        pub fn resolve_with_diagnostics(
+            &mut self,
+            chars: impl Iterator<Item = (char, Type)>,
+            base_level: Option<Level>,
+            levels: &mut [Level],
+            diagnostics: &mut dyn FnMut(Diagnostic),
+        ) -> Result<Level, CapacityError> {
+            let items = char_items(self.options, chars);
+            let mut tracer = Tracer::diagnostics(diagnostics);
+            self.resolve_impl(items, base_level, levels, &mut tracer)
+        }
+
+        fn resolve_impl(
+            &mut self,
+            items: impl Iterator<Item = Item>,
+            base_level: Option<Level>,
+            levels: &mut [Level],
+            tracer: &mut Tracer,
+        ) -> Result<Level, CapacityError> {
+            self.len = 0;
+            self.bracket_count = 0;
+            self.pair_count = 0;
+            self.run_count = 0;
+            self.flags = 0;
+            self.base_level = 0;
+            let mut input = Input::default();
+            tracer.header("Input", "P1");
+            tracer.begin_text();
+            for (i, (ch, t, bracket)) in items.enumerate() {
+                if i == N {
+                    self.len = 0;
+                    return Err(CapacityError);
                 }
-                self.types.truncate(len);
-                self.indices.clear();
-                let mut cur = i;
-                let level = self.runs[i].level;
-                let sos = self.runs[i].sos;
-                let mut eos;
-                loop {
-                    let run = &self.runs[cur];
-                    for i in run.start..run.end {
-                        let ty = self.types[i];
-                        if !is_removed_by_x9(ty) {
-                            self.types.push(ty);
-                            self.indices.push(i);
-                        }
-                    }
-                    eos = run.eos;
-                    cur = match run.next {
-                        Some(i) => i,
-                        None => break,
-                    };
+                let t = self.options.class(t);
+                self.initial_classes[i] = t;
+                if let Some(bracket) = self.options.bracket(bracket) {
+                    self.brackets[self.bracket_count] = (i, bracket);
+                    self.bracket_count += 1;
                 }
-                self.resolve_sequence(level, sos, eos, resolved);
-            }
-            for i in 0..len {
-                let t = self.initial_classes[i];
-                if t == S || t == B {
-                    resolved.levels[i] = self.base_level;
-                    for j in (0..i).rev() {
-                        let t = self.initial_classes[j];
-                        if t.is_ignored() {
-                            continue;
-                        } else if t.is_resettable() {
-                            resolved.levels[j] = self.base_level;
-                        } else {
-                            break;
-                        }
-                    }
-                } else if t.is_ignored() {
-                    if i == 0 {
-                        resolved.levels[i] = self.base_level;
-                    } else {
-                        resolved.levels[i] = resolved.levels[i - 1];
-                    }
+                input.add(t);
+                self.len += 1;
+                if let Some(ch) = ch {
+                    tracer.text(ch);
                 }
             }
-            for i in (0..len).rev() {
-                let t = self.initial_classes[i];
-                if t.is_ignored() {
-                    continue;
-                } else if t.is_resettable() {
-                    resolved.levels[i] = self.base_level;
-                } else {
-                    break;
+            tracer.end_row();
+            let len = self.len;
+            let levels = match levels.get_mut(..len) {
+                Some(levels) => levels,
+                None => {
+                    self.len = 0;
+                    return Err(CapacityError);
                 }
+            };
+            let initial_classes = &self.initial_classes[..len];
+            tracer.types("Bidi_Class:", initial_classes);
+            self.base_level = input.base_level(initial_classes, base_level, tracer);
+            if input.is_simple(self.base_level) {
+                self.flags |= 1;
+                if let Some(run) = simple_run(initial_classes) {
+                    self.runs[0] = run;
+                    self.run_count = 1;
+                }
+                tracer.header("Resolved levels", "L1");
+                tracer.levels(initial_classes, self.levels());
+                levels.fill(self.base_level);
+                return Ok(self.base_level);
+            }
+            self.types[..len].copy_from_slice(initial_classes);
+            self.final_types[..len].copy_from_slice(initial_classes);
+            let mut paragraph = Paragraph {
+                options: self.options,
+                base_level: self.base_level,
+                initial_classes,
+                brackets: &self.brackets[..self.bracket_count],
+                types: &mut self.types[..len],
+                final_types: &mut self.final_types[..len],
+                seq_types: &mut self.seq_types[..len],
+                indices: &mut self.indices[..len],
+                runs: &mut self.runs[..input.max_runs()],
+                run_count: 0,
+                bracket_pairs: &mut self.bracket_pairs[..self.bracket_count / 2],
+                pair_count: 0,
+            };
+            paragraph.resolve(tracer);
+            self.run_count = paragraph.run_count;
+            self.pair_count = paragraph.pair_count;
+            for (level, resolved) in levels.iter_mut().zip(self.levels()) {
+                *level = resolved;
             }
+            Ok(self.base_level)
         }
 
-        fn default_level(types: &[Type]) -> u8 {
-            let mut isolates = 0;
-            for ty in types {
-                let ty = *ty;
-                match ty {
-                    RLI | LRI | FSI => isolates += 1,
-                    PDI => {
-                        if isolates > 0 {
-                            isolates -= 1;
-                        }
-                    }
-                    L | R | AL => {
-                        if isolates == 0 {
-                            return if ty == L { 0 } else { 1 };
-                        }
-                    }
-                    _ => {}
-                }
+        /// Returns an iterator over the levels of the most recently resolved
+        /// paragraph, derived from the level runs and the resolved types.
+        fn levels(&self) -> Levels<'_> {
+            let len = self.len;
+            let (types, final_types) = if self.flags & 1 != 0 {
+                (&[][..], &[][..])
+            } else {
+                (&self.types[..len], &self.final_types[..len])
+            };
+            Levels::new(
+                self.options,
+                self.base_level,
+                &self.initial_classes[..len],
+                types,
+                final_types,
+                &self.runs[..self.run_count],
+                &self.indices[..len],
+            )
+        }
+    }
+
+    impl<const N: usize> Default for FixedState<'_, N> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Error returned when a paragraph exceeds the capacity of a
+    /// [`FixedState`] or of the provided output buffer.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct CapacityError;
+
+    impl core::fmt::Display for CapacityError {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.write_str("paragraph exceeds the capacity of the bidi state")
+        }
+    }
+
+    /// Summary of the input collected while reading a paragraph.
+    #[derive(Default)]
+    struct Input {
+        needs_bidi: bool,
+        len: usize,
+        controls: usize,
+    }
+
+    impl Input {
+        fn add(&mut self, t: Type) {
+            let mask = t.mask();
+            self.needs_bidi = self.needs_bidi || mask & BIDI_MASK != 0;
+            if mask & (EXPLICIT_MASK | PDF.mask() | PDI.mask() | B.mask()) != 0 {
+                self.controls += 1;
             }
-            0
+            self.len += 1;
         }
 
-        fn default_level_until_pdi(types: &[Type]) -> u8 {
-            let mut isolates = 0;
-            for ty in types {
-                let ty = *ty;
-                match ty {
-                    RLI | LRI | FSI => isolates += 1,
-                    PDI => {
-                        if isolates > 0 {
-                            isolates -= 1;
-                        } else {
-                            return 0;
-                        }
-                    }
-                    L | R | AL => {
-                        if isolates == 0 {
-                            return if ty == L { 0 } else { 1 };
-                        }
-                    }
-                    _ => {}
+        fn base_level(
+            &self,
+            initial_classes: &[Type],
+            base_level: Option<Level>,
+            tracer: &mut Tracer,
+        ) -> Level {
+            let level = base_level.unwrap_or_else(|| default_level(initial_classes));
+            tracer.header("Paragraph embedding level", "P2, P3");
+            tracer.paragraph_level(level);
+            level
+        }
+
+        /// Returns true if the paragraph consists entirely of left-to-right
+        /// text and requires no further processing.
+        fn is_simple(&self, base_level: Level) -> bool {
+            !self.needs_bidi && base_level == 0
+        }
+
+        /// Returns an upper bound on the number of level runs. The level only
+        /// changes at an explicit formatting character or paragraph separator.
+        fn max_runs(&self) -> usize {
+            (self.controls * 2 + 1).min(self.len)
+        }
+    }
+
+    /// Returns the single level run of a paragraph that does not require
+    /// bidirectional processing.
+    fn simple_run(classes: &[Type]) -> Option<Run> {
+        let start = classes.iter().position(|t| !is_removed_by_x9(*t))?;
+        let end = classes.iter().rposition(|t| !is_removed_by_x9(*t))? + 1;
+        let mut run = Run::new(0, start, end);
+        run.sos = L;
+        run.eos = L;
+        Some(run)
+    }
+
+    fn resolved_types<'a>(
+        initial_classes: &'a [Type],
+        final_types: &'a [Type],
+    ) -> impl Iterator<Item = Type> + 'a {
+        let simple = final_types.is_empty();
+        let types = if simple { initial_classes } else { final_types };
+        types
+            .iter()
+            .map(move |&t| if simple && !is_removed_by_x9(t) { L } else { t })
+    }
+
+    /// Options that alter how paragraphs are resolved.
+    #[derive(Copy, Clone, Default)]
+    pub(crate) struct Options<'a> {
+        retain_removed: bool,
+        mode: ReorderingMode,
+        version: AlgorithmVersion,
+        source: Source<'a>,
+    }
+
+    impl Options<'_> {
+        const DEFAULT: Self = Self {
+            retain_removed: false,
+            mode: ReorderingMode::Default,
+            version: AlgorithmVersion::Current,
+            source: Source::Builtin,
+        };
+
+        /// Returns the bidirectional type of a character from the character
+        /// data in use.
+        #[cfg(feature = "alloc")]
+        pub(crate) fn char_class(self, ch: char) -> Type {
+            match self.source {
+                #[cfg(feature = "builtin-data")]
+                Source::Builtin => Type::from_char(ch),
+                #[cfg(not(feature = "builtin-data"))]
+                Source::Builtin => L,
+                Source::Data(data) => data.bidi_class(ch),
+                Source::Provider(provider) => provider.bidi_class(ch),
+            }
+        }
+
+        /// Returns the character with the mirrored glyph of a character from
+        /// the character data in use. Providers do not supply mirrored
+        /// glyphs, so the built in data is used for them.
+        #[cfg(feature = "alloc")]
+        pub(crate) fn char_mirror(self, ch: char) -> Option<char> {
+            match self.source {
+                Source::Data(data) => data.mirror(ch),
+                #[cfg(feature = "builtin-data")]
+                _ => mirror(ch),
+                #[cfg(not(feature = "builtin-data"))]
+                _ => None,
+            }
+        }
+
+        /// Returns the type a character of the specified type is resolved
+        /// with. Before version 6.3, the isolate formatting characters were
+        /// unassigned default ignorable code points of type `BN`.
+        fn class(self, t: Type) -> Type {
+            if self.version == AlgorithmVersion::Unicode6_2 && (is_isolate_initiator(t) || t == PDI)
+            {
+                BN
+            } else {
+                t
+            }
+        }
+
+        /// Returns the paired bracket of a character from the character data
+        /// in use.
+        fn char_bracket(self, ch: char) -> Option<Bracket> {
+            let bracket = match self.source {
+                #[cfg(feature = "builtin-data")]
+                Source::Builtin => BracketType::from_char(ch),
+                #[cfg(not(feature = "builtin-data"))]
+                Source::Builtin => BracketType::None,
+                Source::Data(data) => data.bracket_type(ch),
+                Source::Provider(provider) => provider.bracket_type(ch),
+            };
+            // Brackets are identified by the closing bracket, mapping the
+            // canonical equivalents U+232A and U+3009 to the same one.
+            let id = |ch| match ch {
+                '\u{3009}' => 0x232A,
+                _ => ch as u32,
+            };
+            match bracket {
+                BracketType::Open(close) => Some(Bracket::Open(id(close))),
+                BracketType::Close(_) => Some(Bracket::Close(id(ch))),
+                BracketType::None => None,
+            }
+        }
+
+        /// Returns the paired bracket, or none if rule N0 is not applied.
+        fn bracket(self, bracket: Option<Bracket>) -> Option<Bracket> {
+            if self.version == AlgorithmVersion::Unicode6_2 {
+                None
+            } else {
+                bracket
+            }
+        }
+
+        /// Returns the maximum explicit embedding level.
+        fn max_depth(self) -> u8 {
+            if self.version == AlgorithmVersion::Unicode6_2 {
+                61
+            } else {
+                MAX_STACK as u8
+            }
+        }
+    }
+
+    /// Source of the character properties.
+    #[derive(Copy, Clone, Default)]
+    enum Source<'a> {
+        #[default]
+        Builtin,
+        Data(DataBlob<'a>),
+        Provider(&'a (dyn Provider + Sync)),
+    }
+
+    /// Versions of the bidirectional algorithm that differ in the rules they
+    /// apply.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    pub enum AlgorithmVersion {
+        /// The algorithm as of Unicode 6.3 and later.
+        #[default]
+        Current,
+        /// The algorithm before Unicode 6.3, for predicting how older
+        /// implementations display text.
+        ///
+        /// The isolate formatting characters LRI, RLI, FSI and PDI are
+        /// treated as boundary neutrals, paired brackets are not resolved by
+        /// rule N0 and the maximum explicit embedding level is 61.
+        Unicode6_2,
+    }
+
+    /// Variants of the bidirectional algorithm comparable to the reordering
+    /// modes of ICU.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    pub enum ReorderingMode {
+        /// The standard algorithm.
+        #[default]
+        Default,
+        /// Keeps numbers that directly follow left-to-right text with the
+        /// right-to-left text that directly follows them, mimicking Windows
+        /// XP, like `REORDER_NUMBERS_SPECIAL` in ICU. For example, the digits
+        /// in "abc 123DEF" are displayed to the right of "FED". Only applies
+        /// in sequences at even levels.
+        NumbersSpecial,
+        /// Keeps numbers, along with the neutral characters between and after
+        /// them, with right-to-left text unless there is left-to-right text
+        /// on both sides, like `REORDER_GROUP_NUMBERS_WITH_R` in ICU. Arabic
+        /// numbers are treated like European numbers.
+        GroupNumbersWithR,
+        /// Reorders the directional runs of a line without reversing their
+        /// contents, like `REORDER_RUNS_ONLY` in ICU. This converts logical
+        /// text for one paragraph direction into logical text that displays
+        /// the same way with the other direction.
+        ///
+        /// Levels are resolved as in the standard algorithm. The mode only
+        /// affects [`State::reorder`] and [`FixedState::reorder`].
+        RunsOnly,
+        /// Treats numbers as strong left-to-right characters, like
+        /// `REORDER_INVERSE_NUMBERS_AS_L` in ICU. This is intended for
+        /// resolving visually ordered text.
+        InverseNumbersAsL,
+    }
+
+    /// Computes an ordering for a line according to the reordering mode.
+    fn reorder_line(
+        mode: ReorderingMode,
+        base_level: Level,
+        order: &mut [usize],
+        levels: impl Fn(usize) -> Level,
+    ) {
+        if mode != ReorderingMode::RunsOnly {
+            reorder(order, levels);
+            return;
+        }
+        // Runs alternate between text at the base level and text above it.
+        let mut pos = 0;
+        let mut end = order.len();
+        for i in (0..end).rev() {
+            if i == 0 || (levels(i - 1) > base_level) != (levels(i) > base_level) {
+                for j in i..end {
+                    order[pos] = j;
+                    pos += 1;
                 }
+                end = i;
             }
-            0
         }
+    }
 
-        fn resolve_levels(&mut self, resolved: &mut Resolved<Level>) {
-            let base = self.base_level;
-            let len = self.types.len();
-            resolved.levels.clear();
-            resolved.levels.resize(len, 0);
+    fn removed(initial_classes: &[Type]) -> impl Iterator<Item = bool> + '_ {
+        initial_classes.iter().map(|&t| is_removed_by_x9(t))
+    }
+
+    /// Directional status stack and overflow counters of rules X1-X8.
+    struct Explicit {
+        stack: Stack,
+        max_depth: Level,
+        overflow_isolates: usize,
+        overflow_embedding: usize,
+        valid_isolates: usize,
+    }
+
+    /// Outcome of applying rules X2-X8 to a character.
+    struct ExplicitStep {
+        /// Embedding level of the character. Embedding and override
+        /// initiators, which are removed by rule X9, receive the level of the
+        /// embedding outside of them for section 5.2.
+        level: Level,
+        /// Directional override status that applies to the character.
+        override_status: Type,
+        /// Condition detected at the character, if any.
+        diagnostic: Option<DiagnosticKind>,
+    }
+
+    impl Explicit {
+        fn new(base_level: Level, max_depth: Level) -> Self {
             let mut stack = Stack::new();
-            let mut overflow_isolates = 0;
-            let mut overflow_embedding = 0;
-            let mut valid_isolates = 0;
-            stack.push(base, ON, false);
-            for i in 0..len {
-                let t = self.types[i];
-                let tmask = t.mask();
-                if tmask & EXPLICIT_MASK != 0 {
-                    let is_isolate = tmask & ISOLATE_MASK != 0;
-                    let is_rtl = if t == FSI && i + 1 < len {
-                        Self::default_level_until_pdi(&self.types[i + 1..]) == 1
-                    } else {
-                        tmask & RTL_MASK != 0
-                    };
+            stack.push(base_level, ON, false);
+            Self {
+                stack,
+                max_depth,
+                overflow_isolates: 0,
+                overflow_embedding: 0,
+                valid_isolates: 0,
+            }
+        }
+
+        /// Applies rules X2-X8 to the next character of a paragraph. For
+        /// isolate initiators, `matched` tells whether there is a matching
+        /// PDI. The direction of an FSI is only computed when one is found.
+        fn step(
+            &mut self,
+            t: Type,
+            fsi_is_rtl: impl FnOnce() -> bool,
+            matched: bool,
+        ) -> ExplicitStep {
+            let stack = &mut self.stack;
+            let tmask = t.mask();
+            let mut diagnostic = None;
+            if tmask & EXPLICIT_MASK != 0 {
+                let is_isolate = tmask & ISOLATE_MASK != 0;
+                let is_rtl = if t == FSI {
+                    fsi_is_rtl()
+                } else {
+                    tmask & RTL_MASK != 0
+                };
+                let step = ExplicitStep {
+                    level: stack.embedding_level(),
+                    override_status: stack.override_status(),
+                    diagnostic: None,
+                };
+                let new_level = if is_rtl {
+                    (stack.embedding_level() + 1) | 1
+                } else {
+                    (stack.embedding_level() + 2) & !1
+                };
+                if new_level <= self.max_depth
+                    && self.overflow_isolates == 0
+                    && self.overflow_embedding == 0
+                {
                     if is_isolate {
-                        resolved.levels[i] = stack.embedding_level();
-                        let os = stack.override_status();
-                        if os != ON {
-                            self.types[i] = os;
+                        self.valid_isolates += 1;
+                        if !matched {
+                            diagnostic = Some(DiagnosticKind::UnclosedIsolate);
                         }
                     }
-                    let new_level = if is_rtl {
-                        (stack.embedding_level() + 1) | 1
-                    } else {
-                        (stack.embedding_level() + 2) & !1
-                    };
-                    if new_level <= MAX_STACK as u8
-                        && overflow_isolates == 0
-                        && overflow_embedding == 0
+                    stack.push(
+                        new_level,
+                        if t == LRO {
+                            L
+                        } else if t == RLO {
+                            R
+                        } else {
+                            ON
+                        },
+                        is_isolate,
+                    );
+                } else {
+                    if new_level > self.max_depth
+                        && self.overflow_isolates == 0
+                        && self.overflow_embedding == 0
                     {
-                        if is_isolate {
-                            valid_isolates += 1;
-                        }
-                        stack.push(
-                            new_level,
-                            if t == LRO {
-                                L
-                            } else if t == RLO {
-                                R
-                            } else {
-                                ON
-                            },
-                            is_isolate,
-                        );
-                    } else if is_isolate {
-                        overflow_isolates += 1;
-                    } else if overflow_isolates == 0 {
-                        overflow_embedding += 1;
-                    }
-                } else if t == PDI {
-                    if overflow_isolates > 0 {
-                        overflow_isolates -= 1;
-                    } else if valid_isolates == 0 {
-                        // empty
-                    } else {
-                        overflow_embedding = 0;
-                        while !stack.isolate_status() {
-                            stack.pop();
-                        }
-                        stack.pop();
-                        valid_isolates -= 1;
+                        diagnostic = Some(DiagnosticKind::DepthExceeded);
                     }
-                    resolved.levels[i] = stack.embedding_level();
-                    if stack.override_status() != ON {
-                        self.types[i] = stack.override_status();
+                    if is_isolate {
+                        self.overflow_isolates += 1;
+                    } else if self.overflow_isolates == 0 {
+                        self.overflow_embedding += 1;
                     }
-                } else if t == PDF {
-                    resolved.levels[i] = stack.embedding_level();
-                    if overflow_isolates > 0 {
-                        // empty
-                    } else if overflow_embedding > 0 {
-                        overflow_embedding -= 1;
-                    } else if !stack.isolate_status() && stack.depth >= 2 {
+                }
+                return ExplicitStep { diagnostic, ..step };
+            }
+            if t == PDI {
+                if self.overflow_isolates > 0 {
+                    self.overflow_isolates -= 1;
+                } else if self.valid_isolates == 0 {
+                    diagnostic = Some(DiagnosticKind::UnmatchedPdi);
+                } else {
+                    self.overflow_embedding = 0;
+                    while !stack.isolate_status() {
                         stack.pop();
                     }
-                } else if t == B {
-                    stack.depth = 1;
-                    overflow_isolates = 0;
-                    overflow_embedding = 0;
-                    valid_isolates = 0;
-                    resolved.levels[i] = base;
-                } else if t != BN {
-                    resolved.levels[i] = stack.embedding_level();
-                    if stack.override_status() != ON {
-                        self.types[i] = stack.override_status();
-                    }
+                    stack.pop();
+                    self.valid_isolates -= 1;
+                }
+            } else if t == PDF {
+                if self.overflow_isolates > 0 {
+                    // empty
+                } else if self.overflow_embedding > 0 {
+                    self.overflow_embedding -= 1;
+                } else if !stack.isolate_status() && stack.depth >= 2 {
+                    stack.pop();
+                } else {
+                    diagnostic = Some(DiagnosticKind::UnmatchedPdf);
                 }
+            } else if t == B {
+                stack.depth = 1;
+                self.overflow_isolates = 0;
+                self.overflow_embedding = 0;
+                self.valid_isolates = 0;
+            }
+            ExplicitStep {
+                level: stack.embedding_level(),
+                override_status: stack.override_status(),
+                diagnostic,
             }
         }
+    }
 
-        fn resolve_runs(&mut self, resolved: &mut Resolved<Level>) {
-            let len = self.types.len();
-            self.runs.clear();
-            let mut start = 0;
-            while start < len {
-                if !is_removed_by_x9(self.types[start]) {
-                    break;
+    /// Storage for resolving a single paragraph, borrowed from a state.
+    struct Paragraph<'a> {
+        options: Options<'a>,
+        base_level: u8,
+        initial_classes: &'a [Type],
+        brackets: &'a [(usize, Bracket)],
+        types: &'a mut [Type],
+        final_types: &'a mut [Type],
+        seq_types: &'a mut [Type],
+        indices: &'a mut [usize],
+        runs: &'a mut [Run],
+        run_count: usize,
+        bracket_pairs: &'a mut [BracketPair],
+        pair_count: usize,
+    }
+
+    impl<'a> Paragraph<'a> {
+        fn resolve(&mut self, tracer: &mut Tracer) {
+            self.resolve_levels(tracer);
+            tracer.header("Explicit levels and directions", "X1-X8");
+            tracer.types("Bidi_Class:", self.types);
+            self.trace_levels(tracer, false);
+            self.resolve_runs();
+            if tracer.is_enabled() {
+                tracer.header("Isolating run sequences", "BD13, X10");
+                let sequences = Sequences {
+                    runs: &self.runs[..self.run_count],
+                    pos: 0,
+                };
+                for (index, sequence) in sequences.enumerate() {
+                    tracer.sequence(index, sequence.level(), sequence.sos(), sequence.eos());
+                    for range in sequence.ranges() {
+                        tracer.sequence_run(range.start, range.end);
+                    }
                 }
-                start += 1;
             }
-            if start == len {
-                return;
-            }
-            let mut level = resolved.levels[start];
-            let mut offset = 0;
-            for i in start + 1..len {
-                if is_removed_by_x9(self.types[i]) {
+            let mut index = 0;
+            for i in 0..self.run_count {
+                if self.runs[i].in_sequence {
                     continue;
                 }
-                if resolved.levels[i] != level {
-                    self.runs.push(Run::new(level, offset, i));
-                    offset = i;
-                    level = resolved.levels[i];
+                let mut count = 0;
+                let mut cur = i;
+                let level = self.runs[i].level;
+                let sos = self.runs[i].sos;
+                let mut eos;
+                loop {
+                    let run = &self.runs[cur];
+                    for i in run.start..run.end {
+                        let ty = self.types[i];
+                        if !is_removed_by_x9(ty) {
+                            self.seq_types[count] = ty;
+                            self.indices[count] = i;
+                            count += 1;
+                        }
+                    }
+                    eos = run.eos;
+                    cur = match run.next {
+                        Some(i) => i,
+                        None => break,
+                    };
                 }
+                self.resolve_sequence(index, count, level, sos, eos, tracer);
+                index += 1;
             }
-            if offset < len {
-                self.runs.push(Run::new(level, offset, len));
+            self.bracket_pairs[..self.pair_count].sort_unstable_by_key(|pair| pair.open);
+            tracer.header("Implicit levels", "I1, I2");
+            self.trace_levels(tracer, true);
+            if self.options.retain_removed {
+                // The explicit levels of the removed characters are computed
+                // again along with the final levels, which needs the
+                // directions of the first strong isolates.
+                match_isolates(self.initial_classes, self.indices);
             }
-            for run in &mut self.runs {
-                while run.start < run.end {
-                    if is_removed_by_x9(self.types[run.start]) {
-                        run.start += 1;
-                    } else {
-                        break;
-                    }
+            tracer.header("Resolved levels", "L1");
+            tracer.levels(self.initial_classes, self.levels());
+        }
+
+        /// Returns an iterator over the final levels of the paragraph.
+        fn levels(&self) -> Levels<'_> {
+            Levels::new(
+                self.options,
+                self.base_level,
+                self.initial_classes,
+                self.types,
+                self.final_types,
+                &self.runs[..self.run_count],
+                self.indices,
+            )
+        }
+
+        /// Writes the levels of the characters that are not removed by rule
+        /// X9 to the trace, either the explicit levels of their level runs
+        /// or the levels after rules I1 and I2.
+        fn trace_levels(&self, tracer: &mut Tracer, implicit: bool) {
+            let runs = &self.runs[..self.run_count];
+            let mut run = 0;
+            let levels = self.initial_classes.iter().enumerate().map(|(i, &t)| {
+                if is_removed_by_x9(t) {
+                    return self.base_level;
                 }
-                while run.end > run.start {
-                    if is_removed_by_x9(self.types[run.end - 1]) {
-                        run.end -= 1;
-                    } else {
-                        break;
-                    }
+                while runs[run].end <= i {
+                    run += 1;
+                }
+                if implicit {
+                    implicit_level(runs[run].level, self.final_types[i])
+                } else {
+                    runs[run].level
+                }
+            });
+            tracer.levels(self.initial_classes, levels);
+        }
+
+        /// Applies rules X1-X8 and builds the level runs (BD7) from the
+        /// levels of the characters that are not removed by rule X9.
+        fn resolve_levels(&mut self, tracer: &mut Tracer) {
+            let len = self.types.len();
+            let classes = self.initial_classes;
+            match_isolates(classes, self.indices);
+            let indices = &*self.indices;
+            let mut explicit = Explicit::new(self.base_level, self.options.max_depth());
+            self.run_count = 0;
+            for i in 0..len {
+                let t = self.types[i];
+                let step = explicit.step(
+                    t,
+                    || first_strong_is_rtl(classes, indices, i),
+                    indices[i] != len,
+                );
+                if let Some(kind) = step.diagnostic {
+                    tracer.diagnostic(Diagnostic { kind, index: i });
                 }
-                if run.start == run.end {
+                if is_removed_by_x9(t) {
                     continue;
                 }
-                if self.types[run.start] == PDI {
-                    run.starts_with_pdi = true;
+                if step.override_status != ON {
+                    self.types[i] = step.override_status;
                 }
-                let mut prev_level = self.base_level;
-                for i in (0..run.start).rev() {
-                    if !is_removed_by_x9(self.types[i]) {
-                        prev_level = resolved.levels[i];
-                        break;
-                    }
+                let count = self.run_count;
+                if count > 0 && self.runs[count - 1].level == step.level {
+                    self.runs[count - 1].end = i + 1;
+                } else {
+                    self.runs[count] = Run::new(step.level, i, i + 1);
+                    self.run_count += 1;
                 }
-                run.sos = class_from_level(prev_level.max(run.level));
+            }
+        }
+
+        /// Determines the sos and eos of each level run (X10) and links the
+        /// runs that form isolating run sequences (BD13).
+        fn resolve_runs(&mut self) {
+            let len = self.types.len();
+            let base = self.base_level;
+            let runs = &mut self.runs[..self.run_count];
+            for k in 0..runs.len() {
+                let level = runs[k].level;
+                let prev_level = if k > 0 { runs[k - 1].level } else { base };
+                let next_level = runs.get(k + 1).map_or(base, |next| next.level);
+                let run = &mut runs[k];
+                run.sos = class_from_level(prev_level.max(level));
+                run.starts_with_pdi = self.types[run.start] == PDI;
                 if is_isolate_initiator(self.initial_classes[run.end - 1]) {
                     run.ends_with_isolate = true;
-                    run.eos = class_from_level(self.base_level.max(run.level));
+                    run.eos = class_from_level(base.max(level));
                 } else {
-                    let mut next_level = self.base_level;
-                    for i in run.end..len {
-                        if !is_removed_by_x9(self.types[i]) {
-                            next_level = resolved.levels[i];
-                            break;
-                        }
-                    }
-                    run.eos = class_from_level(next_level.max(run.level));
+                    run.eos = class_from_level(next_level.max(level));
                 }
             }
-            for i in 0..self.runs.len() {
-                if self.runs[i].ends_with_isolate {
-                    let level = self.runs[i].level;
-                    for j in i + 1..self.runs.len() {
-                        if self.runs[j].starts_with_pdi && self.runs[j].level == level {
-                            self.runs[i].next = Some(j);
-                            self.runs[j].in_sequence = true;
-                            break;
-                        }
-                    }
+            // Link each run ending with an isolate initiator to the run that
+            // starts with its matching PDI. The matches from `match_isolates`
+            // are stored at the initiators, which leaves the entries at the
+            // PDIs free to record the run that each of them starts.
+            for (index, run) in self.runs[..self.run_count].iter().enumerate() {
+                if run.starts_with_pdi {
+                    self.indices[run.start] = index;
+                }
+            }
+            for i in 0..self.run_count {
+                if !self.runs[i].ends_with_isolate {
+                    continue;
+                }
+                let pdi = self.indices[self.runs[i].end - 1];
+                if pdi == len {
+                    continue;
+                }
+                let j = self.indices[pdi];
+                if j > i
+                    && j < self.run_count
+                    && self.runs[j].start == pdi
+                    && self.runs[j].starts_with_pdi
+                    && self.runs[j].level == self.runs[i].level
+                {
+                    self.runs[i].next = Some(j);
+                    self.runs[j].in_sequence = true;
                 }
             }
         }
 
         fn resolve_sequence(
             &mut self,
+            index: usize,
+            len: usize,
             level: u8,
             sos: Type,
             eos: Type,
-            resolved: &mut Resolved<Level>,
+            tracer: &mut Tracer,
         ) {
-            let len = self.indices.len();
             if len == 0 {
                 return;
             }
+            let indices = &self.indices[..len];
+            let trace = |tracer: &mut Tracer, title, rules, types: &[Type]| {
+                if tracer.is_enabled() {
+                    tracer.header(title, rules);
+                    tracer.sequence(index, level, sos, eos);
+                    tracer.sequence_types(indices, &types[..len]);
+                }
+            };
             const W1_MASK: u32 = LRI.mask() | RLI.mask() | FSI.mask() | PDI.mask();
             const W2_MASK: u32 = L.mask() | R.mask() | AL.mask();
             const W4_MASK: u32 = ES.mask() | CS.mask();
             let mut prev = sos;
             let mut prev_strong = prev;
-            let types = &mut self.types[self.initial_classes.len()..];
+            let types = &mut self.seq_types[..len];
+            let final_types = &mut *self.final_types;
+            if self.options.mode == ReorderingMode::InverseNumbersAsL {
+                for t in types.iter_mut() {
+                    if *t == EN || *t == AN {
+                        *t = L;
+                    }
+                }
+            }
             for i in 0..len {
                 let mut t = types[i];
                 let tmask = t.mask();
@@ -442,6 +1401,7 @@ pub mod state {
                     prev = t;
                 }
             }
+            trace(tracer, "Weak types", "W1-W4", types);
             // W5
             let mut i = 0;
             while i < len {
@@ -460,56 +1420,110 @@ pub mod state {
                 }
                 i += 1;
             }
+            trace(tracer, "Weak types", "W5", types);
             // W6, W7
             const W6_MASK: u32 = ES.mask() | ET.mask() | CS.mask();
-            prev_strong = sos;
+            let mode = self.options.mode;
+            // The legacy modes also move Arabic numbers to the left-to-right
+            // text around them, but only under narrower conditions.
+            let special = mode == ReorderingMode::NumbersSpecial && level & 1 == 0;
+            let grouped = mode == ReorderingMode::GroupNumbersWithR;
+            let number_mask = if special || grouped {
+                EN.mask() | AN.mask()
+            } else {
+                EN.mask()
+            };
             for t in &mut types[..len] {
                 if t.mask() & W6_MASK != 0 {
                     // W6
                     *t = ON;
-                } else if *t == EN {
+                }
+            }
+            prev_strong = sos;
+            let mut i = 0;
+            while i < len {
+                let t = types[i];
+                if t.mask() & number_mask != 0 {
                     // W7
-                    if prev_strong == L {
-                        *t = L;
+                    let limit = find_limit_by_mask(types, i, number_mask);
+                    let to_l = prev_strong == L
+                        && if special {
+                            // Numbers directly followed by right-to-left text
+                            // are kept with it.
+                            let next = if limit == len { eos } else { types[limit] };
+                            next != R
+                        } else if grouped {
+                            // Numbers are kept with right-to-left text unless
+                            // there is left-to-right text on both sides.
+                            let next = types[limit..]
+                                .iter()
+                                .find(|t| **t == L || **t == R)
+                                .copied()
+                                .unwrap_or(eos);
+                            next == L
+                        } else {
+                            true
+                        };
+                    if to_l {
+                        for t in &mut types[i..limit] {
+                            *t = L;
+                        }
                     }
-                } else if *t == L || *t == R {
-                    prev_strong = *t;
+                    i = limit;
+                    continue;
+                } else if t == L || t == R {
+                    prev_strong = t;
                 }
+                i += 1;
             }
+            trace(tracer, "Weak types", "W6, W7", types);
             // N0
             if !self.brackets.is_empty() {
-                let base_brackets = self.bracket_pairs.len();
+                let base_brackets = self.pair_count;
                 let mut bracket_stack = BracketStack::new();
+                // The brackets and the characters of the sequence are both in
+                // logical order, so each bracket is found by advancing a
+                // cursor. The brackets of nested isolates are skipped with a
+                // search that takes time logarithmic in their number.
+                let mut cursor = 0;
                 for (i, &t) in types.iter().enumerate().take(len) {
                     if t != ON {
                         continue;
                     }
                     let index = self.indices[i];
-                    if let Ok(index) = self.brackets.binary_search_by(|x| x.0.cmp(&index)) {
-                        let (_, ch, bracket) = self.brackets[index];
-                        match bracket {
-                            BracketType::Open(closer) => {
+                    cursor = seek_bracket(self.brackets, cursor, index);
+                    if self.brackets.get(cursor).map(|x| x.0) == Some(index) {
+                        match self.brackets[cursor].1 {
+                            Bracket::Open(id) => {
                                 if bracket_stack.depth == MAX_BRACKET_STACK {
+                                    tracer.diagnostic(Diagnostic {
+                                        kind: DiagnosticKind::BracketStackOverflow,
+                                        index: self.indices[i],
+                                    });
                                     break;
                                 }
-                                bracket_stack.push(i, closer);
+                                bracket_stack.push(i, id);
                             }
-                            BracketType::Close(_) => {
-                                if let Some(open) = bracket_stack.find_and_pop(ch) {
-                                    self.bracket_pairs.push((open, i));
+                            Bracket::Close(id) => {
+                                if let Some(open) = bracket_stack.find_and_pop(id) {
+                                    self.bracket_pairs[self.pair_count] = BracketPair {
+                                        open,
+                                        close: i,
+                                        direction: ON,
+                                    };
+                                    self.pair_count += 1;
                                 }
                             }
-                            _ => {}
                         }
                     }
                 }
-                if self.bracket_pairs.len() > base_brackets {
+                if self.pair_count > base_brackets {
                     let embed_dir = if level & 1 != 0 { R } else { L };
-                    let bracket_pairs = &mut self.bracket_pairs[base_brackets..];
-                    bracket_pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                    let bracket_pairs = &mut self.bracket_pairs[base_brackets..self.pair_count];
+                    bracket_pairs.sort_unstable_by_key(|pair| pair.open);
                     for pair in bracket_pairs {
                         let mut pair_dir = ON;
-                        for &ty in &types[pair.0 + 1..pair.1] {
+                        for &ty in &types[pair.open + 1..pair.close] {
                             let dir = match ty {
                                 EN | AN | AL | R => R,
                                 L => L,
@@ -524,13 +1538,13 @@ pub mod state {
                             }
                         }
                         if pair_dir == ON {
-                            pair.0 = self.indices[pair.0];
-                            pair.1 = self.indices[pair.1];
+                            pair.open = self.indices[pair.open];
+                            pair.close = self.indices[pair.close];
                             continue;
                         }
                         if pair_dir != embed_dir {
                             pair_dir = sos;
-                            for i in (0..pair.0).rev() {
+                            for i in (0..pair.open).rev() {
                                 let dir = match types[i] {
                                     EN | AN | AL | R => R,
                                     L => L,
@@ -545,9 +1559,10 @@ pub mod state {
                                 pair_dir = embed_dir;
                             }
                         }
-                        types[pair.0] = pair_dir;
-                        types[pair.1] = pair_dir;
-                        let pair_range = pair.0 + 1..pair.1;
+                        pair.direction = pair_dir;
+                        types[pair.open] = pair_dir;
+                        types[pair.close] = pair_dir;
+                        let pair_range = pair.open + 1..pair.close;
                         for (ty, index) in types[pair_range.clone()]
                             .iter_mut()
                             .zip(&self.indices[pair_range])
@@ -558,7 +1573,7 @@ pub mod state {
                                 break;
                             }
                         }
-                        let pair_range = pair.1 + 1..len;
+                        let pair_range = pair.close + 1..len;
                         for (ty, index) in types[pair_range.clone()]
                             .iter_mut()
                             .zip(&self.indices[pair_range])
@@ -569,11 +1584,12 @@ pub mod state {
                                 break;
                             }
                         }
-                        pair.0 = self.indices[pair.0];
-                        pair.1 = self.indices[pair.1];
+                        pair.open = self.indices[pair.open];
+                        pair.close = self.indices[pair.close];
                     }
                 }
             }
+            trace(tracer, "Paired brackets", "N0", types);
             // N1, N2
             const N_MASK: u32 = B.mask()
                 | S.mask()
@@ -623,30 +1639,642 @@ pub mod state {
                     }
                     i = limit - 1;
                 }
-                i += 1;
+                i += 1;
+            }
+            trace(tracer, "Neutral types", "N1, N2", types);
+            // Keep the final types for the paragraph
+            for (t, index) in types[..len].iter().zip(&self.indices[..len]) {
+                final_types[*index] = *t;
+            }
+        }
+    }
+
+    /// Returns the level of a character with the specified final type in a
+    /// level run at the specified level (I1, I2).
+    fn implicit_level(level: Level, t: Type) -> Level {
+        if level & 1 == 0 {
+            match t {
+                R => level + 1,
+                L => level,
+                _ => level + 2,
+            }
+        } else if t == R {
+            level
+        } else {
+            level + 1
+        }
+    }
+
+    /// Iterator over the final levels of a resolved paragraph in logical
+    /// order.
+    ///
+    /// The levels are derived from the level runs and the final types as
+    /// they are requested, applying rules I1, I2 and L1, so no level is
+    /// stored per character. Characters removed by rule X9 receive the level
+    /// of the preceding character unless they are retained. Retained ones
+    /// are resolved as described in section 5.2, for which their explicit
+    /// levels are computed again.
+    struct Levels<'a> {
+        options: Options<'a>,
+        base_level: Level,
+        initial_classes: &'a [Type],
+        /// Types after rules X1-X8, or empty for a paragraph resolved
+        /// without bidirectional processing.
+        types: &'a [Type],
+        final_types: &'a [Type],
+        runs: &'a [Run],
+        /// Matching PDIs of the isolate initiators, for retained characters.
+        indices: &'a [usize],
+        pos: usize,
+        /// Index of the run containing the last character that was not
+        /// removed.
+        run: usize,
+        /// Level of the last character that was not removed before rule L1,
+        /// or the base level after a separator.
+        carry: Level,
+        /// End of the current sequence of whitespace, isolate formatting and
+        /// removed characters, and whether rule L1 resets it.
+        span_end: usize,
+        span_reset: bool,
+        /// Directional status at the current position and at the end of the
+        /// current block of retained removed characters.
+        current: Explicit,
+        ahead: Explicit,
+        ahead_pos: usize,
+        block: RemovedBlock,
+    }
+
+    /// Block of consecutive removed characters that are retained. Each part
+    /// at the same explicit level keeps that level unless it adjoins,
+    /// within a level run, a character it would be resolved along with by
+    /// rules W5, W6 and N1-N2: a European number or terminator, a separator
+    /// or a neutral. It then receives the level of that character, so the
+    /// levels of the other characters are the same as when the removed
+    /// characters are skipped.
+    #[derive(Default)]
+    struct RemovedBlock {
+        end: usize,
+        /// End of the leading part and the level it receives from the
+        /// preceding character, if any.
+        first_end: usize,
+        first_level: Option<Level>,
+        /// Start of the trailing part and the level it receives from the
+        /// following character, if any.
+        last_start: usize,
+        last_level: Option<Level>,
+    }
+
+    impl<'a> Levels<'a> {
+        fn new(
+            options: Options<'a>,
+            base_level: Level,
+            initial_classes: &'a [Type],
+            types: &'a [Type],
+            final_types: &'a [Type],
+            runs: &'a [Run],
+            indices: &'a [usize],
+        ) -> Self {
+            Self {
+                options,
+                base_level,
+                initial_classes,
+                types,
+                final_types,
+                runs,
+                indices,
+                pos: 0,
+                run: 0,
+                carry: base_level,
+                span_end: 0,
+                span_reset: false,
+                current: Explicit::new(base_level, options.max_depth()),
+                ahead: Explicit::new(base_level, options.max_depth()),
+                ahead_pos: 0,
+                block: RemovedBlock::default(),
+            }
+        }
+
+        /// Returns true if rule L1 resets the character at the specified
+        /// position, which is whitespace, an isolate formatting character or
+        /// a removed character.
+        fn resets(&mut self, i: usize) -> bool {
+            if i >= self.span_end {
+                let classes = self.initial_classes;
+                let mut end = i;
+                while end < classes.len()
+                    && (classes[end].is_ignored() || classes[end].is_resettable())
+                {
+                    end += 1;
+                }
+                self.span_end = end;
+                self.span_reset = classes.get(end).is_none_or(|&t| t == S || t == B);
+            }
+            self.span_reset
+        }
+
+        /// Applies rules X2-X8 to the character at the specified position
+        /// with the specified directional status.
+        fn explicit_level(&self, explicit: &mut Explicit, i: usize) -> Level {
+            let classes = self.initial_classes;
+            let indices = self.indices;
+            explicit
+                .step(
+                    classes[i],
+                    || first_strong_is_rtl(classes, indices, i),
+                    true,
+                )
+                .level
+        }
+
+        /// Determines the levels of the block of retained removed characters
+        /// starting at the specified position (section 5.2).
+        fn begin_block(&mut self, start: usize) {
+            const ADOPT_MASK: u32 = EN.mask()
+                | ET.mask()
+                | ES.mask()
+                | CS.mask()
+                | B.mask()
+                | S.mask()
+                | WS.mask()
+                | ON.mask()
+                | RLI.mask()
+                | LRI.mask()
+                | FSI.mask()
+                | PDI.mask();
+            const NUMBER_MASK: u32 = EN.mask() | ET.mask();
+            let len = self.initial_classes.len();
+            let mut ahead = core::mem::replace(&mut self.ahead, Explicit::new(0, 0));
+            while self.ahead_pos < start {
+                self.explicit_level(&mut ahead, self.ahead_pos);
+                self.ahead_pos += 1;
+            }
+            let first = self.explicit_level(&mut ahead, start);
+            let mut first_end = None;
+            let mut last_start = start;
+            let mut last = first;
+            let mut end = start + 1;
+            while end < len && is_removed_by_x9(self.initial_classes[end]) {
+                let level = self.explicit_level(&mut ahead, end);
+                if level != last {
+                    first_end.get_or_insert(end);
+                    last_start = end;
+                    last = level;
+                }
+                end += 1;
+            }
+            self.ahead = ahead;
+            self.ahead_pos = end;
+            let first_end = first_end.unwrap_or(end);
+            let (types, final_types, runs) = (self.types, self.final_types, self.runs);
+            let adopts = |j: usize| types[j].mask() & ADOPT_MASK != 0 && final_types[j] != AN;
+            let prev = if start > 0 {
+                Some((start - 1, runs[self.run].level))
+            } else {
+                None
+            };
+            let next = if end < len {
+                let run = if runs[self.run].end > end {
+                    self.run
+                } else {
+                    self.run + 1
+                };
+                Some((end, runs[run].level))
+            } else {
+                None
+            };
+            let level_of = |(j, level): (usize, Level)| implicit_level(level, final_types[j]);
+            let prev = prev.filter(|&(p, level)| level == first && adopts(p));
+            let first_next =
+                next.filter(|&(n, level)| first_end == end && level == first && adopts(n));
+            // Numbers are preferred as rule W5 applies first.
+            let first_target = match (prev, first_next) {
+                (Some((p, _)), Some(n))
+                    if types[p].mask() & NUMBER_MASK == 0
+                        && types[n.0].mask() & NUMBER_MASK != 0 =>
+                {
+                    Some(n)
+                }
+                (Some(p), _) => Some(p),
+                (None, next) => next,
+            };
+            let first_level = first_target.map(level_of);
+            let last_level = if first_end == end {
+                first_level
+            } else {
+                next.filter(|&(n, level)| level == last && adopts(n))
+                    .map(level_of)
+            };
+            self.block = RemovedBlock {
+                end,
+                first_end,
+                first_level,
+                last_start,
+                last_level,
+            };
+        }
+    }
+
+    impl Iterator for Levels<'_> {
+        type Item = Level;
+
+        fn next(&mut self) -> Option<Level> {
+            let i = self.pos;
+            let t = *self.initial_classes.get(i)?;
+            self.pos += 1;
+            let base = self.base_level;
+            if self.types.is_empty() {
+                return Some(base);
+            }
+            let retain = self.options.retain_removed;
+            let explicit = if retain {
+                let mut current = core::mem::replace(&mut self.current, Explicit::new(0, 0));
+                let level = self.explicit_level(&mut current, i);
+                self.current = current;
+                level
+            } else {
+                base
+            };
+            if !is_removed_by_x9(t) {
+                while self.runs[self.run].end <= i {
+                    self.run += 1;
+                }
+                if t == S || t == B {
+                    self.carry = base;
+                    return Some(base);
+                }
+                let level = implicit_level(self.runs[self.run].level, self.final_types[i]);
+                self.carry = level;
+                if t.is_resettable() && self.resets(i) {
+                    return Some(base);
+                }
+                return Some(level);
+            }
+            if !retain {
+                return Some(self.carry);
+            }
+            if i >= self.block.end {
+                self.begin_block(i);
+            }
+            let block = &self.block;
+            let level = if i < block.first_end {
+                block.first_level.unwrap_or(explicit)
+            } else if i >= block.last_start {
+                block.last_level.unwrap_or(explicit)
+            } else {
+                explicit
+            };
+            Some(if self.resets(i) { base } else { level })
+        }
+    }
+
+    /// Paired bracket role of an item resolved without characters. An
+    /// opening and a closing bracket with the same identifier form a pair
+    /// (BD16).
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum Bracket {
+        /// Opening bracket of the pair with the specified identifier.
+        Open(u32),
+        /// Closing bracket of the pair with the specified identifier.
+        Close(u32),
+    }
+
+    /// Returns the index of the first bracket at or after the specified
+    /// position, searching forward from `from` in exponentially growing
+    /// steps.
+    fn seek_bracket(brackets: &[(usize, Bracket)], from: usize, pos: usize) -> usize {
+        let mut lo = from;
+        let mut hi = from;
+        let mut step = 1;
+        while hi < brackets.len() && brackets[hi].0 < pos {
+            lo = hi + 1;
+            hi += step;
+            step *= 2;
+        }
+        let hi = hi.min(brackets.len());
+        lo + brackets[lo..hi].partition_point(|x| x.0 < pos)
+    }
+
+    /// Input item: the character, if any, its type and its paired bracket.
+    type Item = (Option<char>, Type, Option<Bracket>);
+
+    /// Adds the paired bracket of each character from the character data
+    /// in use.
+    fn char_items<'a>(
+        options: Options<'a>,
+        chars: impl Iterator<Item = (char, Type)> + 'a,
+    ) -> impl Iterator<Item = Item> + 'a {
+        chars.map(move |(ch, t)| (Some(ch), t, options.char_bracket(ch)))
+    }
+
+    /// Pair of brackets matched by rule BD16.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct BracketPair {
+        /// Index of the opening bracket.
+        pub open: usize,
+        /// Index of the closing bracket.
+        pub close: usize,
+        /// Direction assigned to both brackets by rule N0. This is `L` or `R`
+        /// if the pair was resolved, or `ON` if no strong type was found
+        /// between the brackets and the pair was left for rules N1 and N2.
+        pub direction: Type,
+    }
+
+    impl BracketPair {
+        const EMPTY: Self = Self {
+            open: 0,
+            close: 0,
+            direction: ON,
+        };
+    }
+
+    /// Condition detected while resolving a paragraph that may indicate
+    /// malformed text.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct Diagnostic {
+        /// Kind of the condition.
+        pub kind: DiagnosticKind,
+        /// Index of the character where the condition was detected.
+        pub index: usize,
+    }
+
+    /// Kind of a [`Diagnostic`].
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum DiagnosticKind {
+        /// An embedding, override or isolate initiator would exceed the
+        /// maximum depth, so it and everything nested inside it up to its
+        /// matching PDF or PDI are ignored (X5a-X5c and X2-X5). Only the
+        /// outermost such initiator is reported.
+        DepthExceeded,
+        /// A PDF that does not close an embedding or override (X7).
+        UnmatchedPdf,
+        /// A PDI that does not close an isolate (X6a).
+        UnmatchedPdi,
+        /// An isolate initiator without a matching PDI (BD9), so the
+        /// isolate remains open until the end of the paragraph.
+        UnclosedIsolate,
+        /// An opening bracket that does not fit on the bracket stack (BD16),
+        /// so no further brackets are paired in its isolating run sequence.
+        BracketStackOverflow,
+    }
+
+    /// Kind of an explicit directional formatting character.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum EmbeddingKind {
+        /// Embedding started by LRE or RLE.
+        Embedding,
+        /// Override started by LRO or RLO.
+        Override,
+        /// Isolate started by LRI, RLI or FSI.
+        Isolate,
+    }
+
+    /// Explicit embedding, override or isolate in a paragraph.
+    #[cfg(feature = "alloc")]
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub struct Embedding {
+        /// Kind of the initiator.
+        pub kind: EmbeddingKind,
+        /// Direction of the embedding, either `L` or `R`. For FSI, this is
+        /// the direction determined by rule X5c.
+        pub direction: Type,
+        /// Embedding level of the characters inside.
+        pub level: Level,
+        /// Index of the initiator.
+        pub start: usize,
+        /// Index of the PDF or PDI that closed the embedding. If it was
+        /// closed implicitly, this is the index of the enclosing isolate's
+        /// PDI, of the paragraph separator, or the length of the paragraph.
+        pub end: usize,
+        /// True if the embedding was not closed by its own PDF or PDI.
+        pub closed_implicitly: bool,
+        /// Embeddings nested directly inside, in logical order.
+        pub children: Vec<Embedding>,
+    }
+
+    /// Builds the tree of explicit embeddings by simulating the directional
+    /// status stack of rules X1-X8 over the classes of a paragraph.
+    #[cfg(feature = "alloc")]
+    fn embeddings(options: Options, classes: &[Type], base_level: Level) -> Vec<Embedding> {
+        fn close(
+            open: &mut Vec<Embedding>,
+            roots: &mut Vec<Embedding>,
+            end: usize,
+            implicit: bool,
+        ) {
+            if let Some(mut node) = open.pop() {
+                node.end = end;
+                node.closed_implicitly = implicit;
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
             }
-            // Implicit levels
-            if level & 1 == 0 {
-                // I1
-                for (t, index) in types[..len].iter().zip(&self.indices[..len]) {
-                    if *t == R {
-                        resolved.levels[*index] = level + 1;
-                    } else if *t != L {
-                        resolved.levels[*index] = level + 2;
-                    } else {
-                        resolved.levels[*index] = level;
+        }
+        let mut indices = alloc::vec![0; classes.len()];
+        match_isolates(classes, &mut indices);
+        let max_depth = options.max_depth();
+        let mut roots = Vec::new();
+        let mut open: Vec<Embedding> = Vec::new();
+        let mut overflow_isolates = 0;
+        let mut overflow_embedding = 0;
+        let mut valid_isolates = 0;
+        for (i, &t) in classes.iter().enumerate() {
+            let tmask = t.mask();
+            let is_isolate = |node: &Embedding| node.kind == EmbeddingKind::Isolate;
+            if tmask & EXPLICIT_MASK != 0 {
+                let kind = if tmask & ISOLATE_MASK != 0 {
+                    EmbeddingKind::Isolate
+                } else if t == LRO || t == RLO {
+                    EmbeddingKind::Override
+                } else {
+                    EmbeddingKind::Embedding
+                };
+                let is_rtl = if t == FSI {
+                    first_strong_is_rtl(classes, &indices, i)
+                } else {
+                    tmask & RTL_MASK != 0
+                };
+                let level = open.last().map_or(base_level, |node| node.level);
+                let new_level = if is_rtl {
+                    (level + 1) | 1
+                } else {
+                    (level + 2) & !1
+                };
+                if new_level <= max_depth && overflow_isolates == 0 && overflow_embedding == 0 {
+                    if kind == EmbeddingKind::Isolate {
+                        valid_isolates += 1;
                     }
+                    open.push(Embedding {
+                        kind,
+                        direction: if is_rtl { R } else { L },
+                        level: new_level,
+                        start: i,
+                        end: classes.len(),
+                        closed_implicitly: true,
+                        children: Vec::new(),
+                    });
+                } else if kind == EmbeddingKind::Isolate {
+                    overflow_isolates += 1;
+                } else if overflow_isolates == 0 {
+                    overflow_embedding += 1;
                 }
-            } else {
-                // I2
-                for (t, index) in types[..len].iter().zip(&self.indices[..len]) {
-                    if *t != R {
-                        resolved.levels[*index] = level + 1;
-                    } else {
-                        resolved.levels[*index] = level;
+            } else if t == PDI {
+                if overflow_isolates > 0 {
+                    overflow_isolates -= 1;
+                } else if valid_isolates > 0 {
+                    overflow_embedding = 0;
+                    while !open.last().is_none_or(is_isolate) {
+                        close(&mut open, &mut roots, i, true);
                     }
+                    close(&mut open, &mut roots, i, false);
+                    valid_isolates -= 1;
+                }
+            } else if t == PDF {
+                if overflow_isolates > 0 {
+                    // empty
+                } else if overflow_embedding > 0 {
+                    overflow_embedding -= 1;
+                } else if open.last().is_some_and(|node| !is_isolate(node)) {
+                    close(&mut open, &mut roots, i, false);
+                }
+            } else if t == B {
+                while !open.is_empty() {
+                    close(&mut open, &mut roots, i, true);
+                }
+                overflow_isolates = 0;
+                overflow_embedding = 0;
+                valid_isolates = 0;
+            }
+        }
+        while !open.is_empty() {
+            close(&mut open, &mut roots, classes.len(), true);
+        }
+        roots
+    }
+
+    /// Stores the position of the PDI matching each isolate initiator
+    /// (BD9) in `indices`, or the length of the paragraph if there is none.
+    /// While scanning, the entries of the open initiators form a linked stack
+    /// so that the whole pass is linear.
+    fn match_isolates(classes: &[Type], indices: &mut [usize]) {
+        let len = classes.len();
+        let mut top = len;
+        for (i, &t) in classes.iter().enumerate() {
+            if is_isolate_initiator(t) {
+                indices[i] = top;
+                top = i;
+            } else if t == PDI && top != len {
+                let open = top;
+                top = indices[open];
+                indices[open] = i;
+            }
+        }
+        while top != len {
+            let open = top;
+            top = indices[open];
+            indices[open] = len;
+        }
+    }
+
+    /// Determines the direction of the first strong isolate at the
+    /// specified position from the first strong type preceding its matching
+    /// PDI (X5c). Nested isolates are skipped using the matches computed by
+    /// `match_isolates`, so every character is examined by at most one first
+    /// strong isolate.
+    fn first_strong_is_rtl(classes: &[Type], indices: &[usize], index: usize) -> bool {
+        let end = indices[index];
+        let mut i = index + 1;
+        while i < end {
+            match classes[i] {
+                L => return false,
+                R | AL => return true,
+                RLI | LRI | FSI => i = indices[i],
+                _ => {}
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Iterator over the isolating run sequences of a resolved paragraph.
+    #[derive(Clone)]
+    pub struct Sequences<'a> {
+        runs: &'a [Run],
+        pos: usize,
+    }
+
+    impl<'a> Iterator for Sequences<'a> {
+        type Item = Sequence<'a>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while self.pos < self.runs.len() {
+                let first = self.pos;
+                self.pos += 1;
+                if !self.runs[first].in_sequence {
+                    return Some(Sequence {
+                        runs: self.runs,
+                        first,
+                    });
                 }
             }
+            None
+        }
+    }
+
+    /// Isolating run sequence as defined by rule BD13.
+    #[derive(Copy, Clone)]
+    pub struct Sequence<'a> {
+        runs: &'a [Run],
+        first: usize,
+    }
+
+    impl<'a> Sequence<'a> {
+        /// Returns the embedding level of the sequence.
+        pub fn level(&self) -> Level {
+            self.runs[self.first].level
+        }
+
+        /// Returns the type of the start-of-sequence boundary.
+        pub fn sos(&self) -> Type {
+            self.runs[self.first].sos
+        }
+
+        /// Returns the type of the end-of-sequence boundary.
+        pub fn eos(&self) -> Type {
+            let mut cur = self.first;
+            while let Some(next) = self.runs[cur].next {
+                cur = next;
+            }
+            self.runs[cur].eos
+        }
+
+        /// Returns an iterator over the character ranges of the level runs
+        /// that make up the sequence, in logical order.
+        ///
+        /// Characters removed by rule X9 are trimmed from the ends of each
+        /// range but may still occur inside of it.
+        pub fn ranges(&self) -> SequenceRanges<'a> {
+            SequenceRanges {
+                runs: self.runs,
+                cur: Some(self.first),
+            }
+        }
+    }
+
+    /// Iterator over the ranges of the level runs in an isolating run sequence.
+    #[derive(Clone)]
+    pub struct SequenceRanges<'a> {
+        runs: &'a [Run],
+        cur: Option<usize>,
+    }
+
+    impl<'a> Iterator for SequenceRanges<'a> {
+        type Item = Range<usize>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let run = &self.runs[self.cur?];
+            self.cur = run.next;
+            Some(run.start..run.end)
         }
     }
 
@@ -654,6 +2282,7 @@ pub mod state {
     /// Stores the result in preallocated storage.
     ///
     /// Detects the base level if not provided.
+    #[cfg(feature = "alloc")]
     pub fn resolve_levels_into(
         state: &mut State,
         text: &str,
@@ -663,11 +2292,23 @@ pub mod state {
     ) {
         resolved.base_level = 0;
         resolved.levels.clear();
+        resolved.granularity = granularity;
+        if let Some(len) = simple_len(state, text, granularity, base_level) {
+            resolved.levels.resize(len, 0);
+            return;
+        }
+        let options = state.options;
         state.resolve(
-            text.chars().map(|ch| (ch, Type::from_char(ch))),
+            text.chars().map(|ch| (ch, options.char_class(ch))),
             base_level,
             resolved,
         );
+        set_granularity(text, granularity, resolved);
+    }
+
+    /// Converts per character levels to the specified granularity.
+    #[cfg(feature = "alloc")]
+    fn set_granularity(text: &str, granularity: Granularity, resolved: &mut Resolved<Level>) {
         resolved.granularity = granularity;
         match granularity {
             Granularity::Char => {}
@@ -683,9 +2324,41 @@ pub mod state {
         }
     }
 
+    /// Resolves the bidirectional levels for the specified text as part of
+    /// a larger paragraph, given the text that precedes and follows it in
+    /// the paragraph (HL5).
+    ///
+    /// The context affects the levels of the text as it would if the whole
+    /// paragraph were resolved, but only the levels of the text are
+    /// returned. This is similar to `setContext` in ICU. Detects the base
+    /// level from the whole paragraph if not provided.
+    #[cfg(feature = "alloc")]
+    pub fn resolve_levels_with_context(
+        state: &mut State,
+        prefix: &str,
+        text: &str,
+        suffix: &str,
+        granularity: Granularity,
+        base_level: Option<Level>,
+    ) -> Resolved<Level> {
+        let options = state.options;
+        let class = |ch| (ch, options.char_class(ch));
+        let mut resolved = Resolved::default();
+        state.resolve_with_context(
+            prefix.chars().map(class),
+            text.chars().map(class),
+            suffix.chars().map(class),
+            base_level,
+            &mut resolved,
+        );
+        set_granularity(text, granularity, &mut resolved);
+        resolved
+    }
+
     /// Resolves the bidirectional levels for the specified text and granularity.
     ///
     /// Detects the base level if not provided.
+    #[cfg(feature = "alloc")]
     pub fn resolve_levels(
         state: &mut State,
         text: &str,
@@ -705,6 +2378,7 @@ pub mod state {
     /// Stores the result in preallocated storage.
     ///
     /// Detects the base level if not provided.
+    #[cfg(feature = "alloc")]
     pub fn resolve_ranges_into(
         state: &mut State,
         text: &str,
@@ -712,50 +2386,94 @@ pub mod state {
         base_level: Option<Level>,
         resolved: &mut Resolved<LevelRange>,
     ) {
-        let by_char = resolve_levels(state, text, granularity, base_level);
         resolved.granularity = granularity;
-        resolved.base_level = by_char.base_level;
         resolved.levels.clear();
-        let levels = by_char.levels;
-        if levels.is_empty() {
-            return;
+        let levels = &mut resolved.levels;
+        resolved.base_level =
+            resolve_ranges_with(state, text, granularity, base_level, |level, range| {
+                levels.push((level, range))
+            });
+    }
+
+    /// Returns the length of the text in units of the specified granularity
+    /// if it resolves entirely to level 0 without bidirectional processing.
+    ///
+    /// In that case, the state is cleared rather than resolved.
+    #[cfg(feature = "alloc")]
+    fn simple_len(
+        state: &mut State,
+        text: &str,
+        granularity: Granularity,
+        base_level: Option<Level>,
+    ) -> Option<usize> {
+        // The quick check only applies to the built in tables.
+        if base_level.unwrap_or(0) != 0
+            || !matches!(state.options.source, Source::Builtin)
+            || requires_bidi(text)
+        {
+            return None;
         }
-        let mut start = 0;
-        let mut end = 0;
-        let mut last_level = levels[0];
-        match granularity {
-            Granularity::Char => {
-                for level in &levels {
-                    if *level != last_level {
-                        resolved.levels.push((last_level, start..end));
-                        start = end;
-                        last_level = *level;
-                    }
-                    end += 1;
-                }
-                if start < end {
-                    resolved.levels.push((last_level, start..end));
-                }
+        state.clear();
+        Some(match granularity {
+            Granularity::Char => text.chars().count(),
+            Granularity::Byte => text.len(),
+        })
+    }
+
+    /// Resolves the bidirectional level ranges for the specified text and
+    /// granularity, invoking the callback with each level and its associated
+    /// range in logical order.
+    ///
+    /// No level is stored per character: the ranges are derived from the
+    /// level runs and resolved types that the state keeps for the
+    /// paragraph, so no allocation occurs once the state has grown to fit
+    /// the text. Returns the base level, which is detected if not provided.
+    #[cfg(feature = "alloc")]
+    pub fn resolve_ranges_with(
+        state: &mut State,
+        text: &str,
+        granularity: Granularity,
+        base_level: Option<Level>,
+        mut f: impl FnMut(Level, Range<usize>),
+    ) -> Level {
+        if let Some(len) = simple_len(state, text, granularity, base_level) {
+            if len != 0 {
+                f(0, 0..len);
             }
-            Granularity::Byte => {
-                for (level, ch) in levels.iter().zip(text.chars()) {
-                    if *level != last_level {
-                        resolved.levels.push((last_level, start..end));
-                        start = end;
-                        last_level = *level;
-                    }
-                    end += ch.len_utf8();
-                }
-                if start < end {
-                    resolved.levels.push((last_level, start..end));
+            return 0;
+        }
+        let options = state.options;
+        state.resolve_paragraph(
+            char_items(options, text.chars().map(|ch| (ch, options.char_class(ch)))),
+            base_level,
+            &mut Tracer::none(),
+        );
+        let mut levels = state.levels().zip(text.chars());
+        if let Some((first, ch)) = levels.next() {
+            let unit_len = |ch: char| match granularity {
+                Granularity::Char => 1,
+                Granularity::Byte => ch.len_utf8(),
+            };
+            let mut start = 0;
+            let mut end = unit_len(ch);
+            let mut last_level = first;
+            for (level, ch) in levels {
+                if level != last_level {
+                    f(last_level, start..end);
+                    start = end;
+                    last_level = level;
                 }
+                end += unit_len(ch);
             }
+            f(last_level, start..end);
         }
+        state.base_level
     }
 
     /// Resolves the bidirectional level ranges for the specified text and granularity.
     ///
     /// Detects the base level if not provided.
+    #[cfg(feature = "alloc")]
     pub fn resolve_ranges(
         state: &mut State,
         text: &str,
@@ -770,11 +2488,51 @@ pub mod state {
         resolve_ranges_into(state, text, granularity, base_level, &mut resolved);
         resolved
     }
+
+    /// Writes a line of text in visual order using its resolved levels and
+    /// the character data of the state.
+    ///
+    /// See [`write_reordered`](crate::write_reordered) for details.
+    #[cfg(feature = "alloc")]
+    pub fn write_reordered(
+        state: &State,
+        text: &str,
+        resolved: &Resolved<Level>,
+        options: WriteOptions,
+        out: &mut impl core::fmt::Write,
+    ) -> core::fmt::Result {
+        visual::write_reordered_with(state.options, text, resolved, options, out)
+    }
+
+    /// Writes visually ordered text in logical order using the character
+    /// data of the state.
+    ///
+    /// The reordering mode of the state is ignored. See
+    /// [`write_logical`](crate::write_logical) for details.
+    #[cfg(feature = "alloc")]
+    pub fn write_logical(
+        state: &mut State,
+        visual: &str,
+        base_level: Option<Level>,
+        mode: InverseMode,
+        out: &mut impl core::fmt::Write,
+    ) -> core::fmt::Result {
+        let reordering_mode = state.options.mode;
+        state.options.mode = match mode {
+            InverseMode::LikeDirect => ReorderingMode::Default,
+            InverseMode::NumbersAsL => ReorderingMode::InverseNumbersAsL,
+        };
+        let result = visual::write_logical_with(state, visual, base_level, mode, out);
+        state.options.mode = reordering_mode;
+        result
+    }
 }
 
+#[cfg(feature = "alloc")]
 use state::State;
 
 /// Resolved bidirectional levels.
+#[cfg(feature = "alloc")]
 #[derive(Clone)]
 pub struct Resolved<T> {
     /// Granularity at which the levels were resolved.
@@ -785,6 +2543,7 @@ pub struct Resolved<T> {
     pub levels: Vec<T>,
 }
 
+#[cfg(feature = "alloc")]
 impl<T> Default for Resolved<T> {
     fn default() -> Self {
         Self {
@@ -795,6 +2554,7 @@ impl<T> Default for Resolved<T> {
     }
 }
 
+#[cfg(feature = "alloc")]
 impl Resolved<Level> {
     /// Converts a sequence of bidirectional levels into a collection of ranges.
     pub fn to_ranges(&self) -> Resolved<LevelRange> {
@@ -828,6 +2588,15 @@ impl Resolved<Level> {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl Resolved<LevelRange> {
+    /// Returns the level ranges as runs in visual order.
+    pub fn visual_runs(&self) -> Vec<VisualRun> {
+        let mut order = alloc::vec![0; self.levels.len()];
+        reorder_ranges(&self.levels, &mut order).collect()
+    }
+}
+
 /// Defines the granularity at which levels are resolved.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Granularity {
@@ -841,6 +2610,7 @@ pub enum Granularity {
 /// Stores the result in preallocated storage.
 ///
 /// Detects the base level if not provided.
+#[cfg(feature = "alloc")]
 pub fn resolve_levels_into(
     text: &str,
     granularity: Granularity,
@@ -853,6 +2623,7 @@ pub fn resolve_levels_into(
 /// Resolves the bidirectional levels for the specified text and granularity.
 ///
 /// Detects the base level if not provided.
+#[cfg(feature = "alloc")]
 pub fn resolve_levels(
     text: &str,
     granularity: Granularity,
@@ -865,6 +2636,7 @@ pub fn resolve_levels(
 /// Stores the result in preallocated storage.
 ///
 /// Detects the base level if not provided.
+#[cfg(feature = "alloc")]
 pub fn resolve_ranges_into(
     text: &str,
     granularity: Granularity,
@@ -877,6 +2649,7 @@ pub fn resolve_ranges_into(
 /// Resolves the bidirectional level ranges for the specified text and granularity.
 ///
 /// Detects the base level if not provided.
+#[cfg(feature = "alloc")]
 pub fn resolve_ranges(
     text: &str,
     granularity: Granularity,
@@ -885,6 +2658,94 @@ pub fn resolve_ranges(
     state::resolve_ranges(&mut State::new(), text, granularity, base_level)
 }
 
+/// Resolves the bidirectional level ranges for the specified text and
+/// granularity, invoking the callback with each level and its associated
+/// range in logical order.
+///
+/// The buffers of a new state are allocated for the paragraph; see
+/// [`state::resolve_ranges_with`] to reuse them across calls. Returns the
+/// base level, which is detected if not provided.
+#[cfg(feature = "alloc")]
+pub fn resolve_ranges_with(
+    text: &str,
+    granularity: Granularity,
+    base_level: Option<Level>,
+    f: impl FnMut(Level, Range<usize>),
+) -> Level {
+    state::resolve_ranges_with(&mut State::new(), text, granularity, base_level, f)
+}
+
+/// Determines the paragraph level from the first strong type (P2, P3).
+fn default_level(types: &[Type]) -> u8 {
+    let mut isolates = 0;
+    for ty in types {
+        let ty = *ty;
+        match ty {
+            RLI | LRI | FSI => isolates += 1,
+            PDI if isolates > 0 => isolates -= 1,
+            L | R | AL if isolates == 0 => return if ty == L { 0 } else { 1 },
+            _ => {}
+        }
+    }
+    0
+}
+
+/// Returns true if the text may contain characters that require
+/// bidirectional processing: right-to-left letters, Arabic numbers or
+/// explicit directional formatting characters.
+///
+/// This is a fast conservative check, similar to `Bidi.requiresBidi` on
+/// Android, that scans the UTF-8 encoding a word at a time and matches byte
+/// patterns for the blocks containing such characters. It may return true
+/// for text that resolves entirely to level 0, but never returns false for
+/// text that does not.
+pub fn requires_bidi(text: &str) -> bool {
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    let bytes = text.as_bytes();
+    let mut chunks = bytes.chunks_exact(8);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        if u64::from_ne_bytes(word) & HIGH_BITS != 0 {
+            for i in offset..offset + 8 {
+                if may_require_bidi(&bytes[i..]) {
+                    return true;
+                }
+            }
+        }
+        offset += 8;
+    }
+    (offset..bytes.len()).any(|i| may_require_bidi(&bytes[i..]))
+}
+
+/// Returns true if the UTF-8 sequence starting at the beginning of the
+/// slice is in a block that contains characters with a bidirectional type
+/// of R, AL, AN or an explicit formatting type.
+fn may_require_bidi(bytes: &[u8]) -> bool {
+    let byte = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    match byte(0) {
+        // U+0580..U+07FF: Hebrew, Arabic, Syriac, Thaana, NKo
+        0xD6..=0xDF => true,
+        // U+0800..U+08FF: Samaritan, Mandaic, Arabic extended
+        0xE0 => matches!(byte(1), 0xA0..=0xA3),
+        // U+200F, U+202A..U+202E, U+2066..U+2069: marks and controls
+        0xE2 => matches!(
+            (byte(1), byte(2)),
+            (0x80, 0x8F) | (0x80, 0xAA..=0xAE) | (0x81, 0xA6..=0xA9)
+        ),
+        // U+FB00..U+FEFF: Hebrew and Arabic presentation forms
+        0xEF => matches!(byte(1), 0xAC..=0xBB),
+        // U+10800..U+10FFF and U+1E800..U+1EFFF: historic scripts and
+        // Arabic mathematical symbols
+        0xF0 => matches!(
+            (byte(1), byte(2)),
+            (0x90, 0xA0..=0xBF) | (0x9E, 0xA0..=0xBF)
+        ),
+        _ => false,
+    }
+}
+
 /// Returns a default bidi type for a level.
 fn class_from_level(level: u8) -> Type {
     if level & 1 == 0 {
@@ -895,18 +2756,184 @@ fn class_from_level(level: u8) -> Type {
 }
 
 /// Computes an ordering for a sequence of bidirectional levels.
+///
+/// Rather than reversing each run once per level (L2), this maps every
+/// position directly to its visual position, so the running time is linear
+/// in the length of the sequence regardless of how deeply the levels are
+/// nested. This needs one entry per nested level: up to 16 levels are
+/// tracked on the stack and deeper nesting in a heap allocation. Without
+/// the `alloc` feature, sequences with levels above 15 are instead reversed
+/// once per level, which takes time proportional to the length times the
+/// number of levels.
 pub fn reorder(order: &mut [usize], levels: impl Fn(usize) -> Level) {
-    let mut max_level = 0;
-    let mut lowest_odd_level = 255;
     let len = order.len();
-    for (i, pos) in order.iter_mut().enumerate() {
+    let mut lowest_odd_level = 255;
+    let mut max_level = 0;
+    // First, store the next position with a lower level for every position.
+    // Following these links from the next position skips every position
+    // with a higher level for good, so the pass is linear.
+    for i in (0..len).rev() {
         let level = levels(i);
-        if level > max_level {
-            max_level = level;
-        }
         if level & 1 != 0 && level < lowest_odd_level {
             lowest_odd_level = level;
         }
+        max_level = max_level.max(level);
+        let mut next = i + 1;
+        while next < len && levels(next) >= level {
+            next = order[next];
+        }
+        order[i] = next;
+    }
+    // Every span other than the root covers a distinct range of levels
+    // above its parent, so at most one is open per level.
+    if max_level as usize >= INLINE_SPANS {
+        #[cfg(feature = "alloc")]
+        {
+            let mut spans = alloc::vec![Span::ROOT; max_level as usize + 1];
+            map_positions(order, &levels, lowest_odd_level, &mut spans);
+        }
+        #[cfg(not(feature = "alloc"))]
+        {
+            reverse_by_level(order, levels, lowest_odd_level, max_level);
+            return;
+        }
+    } else {
+        let mut spans = [Span::ROOT; INLINE_SPANS];
+        map_positions(order, &levels, lowest_odd_level, &mut spans);
+    }
+    // Finally, invert the permutation in place, marking the entries that
+    // have already been written.
+    const MARK: usize = !(usize::MAX >> 1);
+    for start in 0..len {
+        if order[start] & MARK != 0 {
+            continue;
+        }
+        let mut i = start;
+        let mut pos = order[start];
+        loop {
+            let next = order[pos];
+            order[pos] = i | MARK;
+            if pos == start {
+                break;
+            }
+            i = pos;
+            pos = next;
+        }
+    }
+    for pos in order.iter_mut() {
+        *pos &= !MARK;
+    }
+}
+
+/// Number of spans that `reorder` keeps on the stack.
+const INLINE_SPANS: usize = 16;
+
+/// Nested span of positions at or above a range of levels. Its positions
+/// map to visual positions as `offset + i`, or `offset - i` if reversed an
+/// odd number of times, composed with the mapping of its parent.
+#[derive(Copy, Clone)]
+struct Span {
+    /// Level below the span, which ends at the first position at or below
+    /// this level.
+    floor: Level,
+    /// Highest level covered by the span.
+    level: Level,
+    offset: isize,
+    reversed: bool,
+}
+
+impl Span {
+    const ROOT: Self = Self {
+        floor: 0,
+        level: 0,
+        offset: 0,
+        reversed: false,
+    };
+}
+
+/// Replaces the links to the next lower position computed by `reorder`
+/// with the visual position of each position, walking the nested spans of
+/// positions at or above each level.
+fn map_positions(
+    order: &mut [usize],
+    levels: &impl Fn(usize) -> Level,
+    lowest_odd_level: Level,
+    spans: &mut [Span],
+) {
+    let len = order.len();
+    let mut depth = 1;
+    for i in 0..len {
+        let level = levels(i);
+        while depth > 1 && level <= spans[depth - 1].floor {
+            depth -= 1;
+        }
+        let parent_level = spans[depth - 1].level;
+        if level > parent_level {
+            // The spans beginning here are found from innermost to outermost
+            // by following the links to lower levels. Count them first so
+            // they can be stored outermost first, with the end of each span
+            // held in its offset until the offset is computed.
+            let mut count = 1;
+            let mut end = order[i];
+            while end < len && levels(end) > parent_level {
+                count += 1;
+                end = order[end];
+            }
+            let mut high = level;
+            let mut end = order[i];
+            for k in (depth..depth + count).rev() {
+                let floor = if end < len {
+                    levels(end).max(parent_level)
+                } else {
+                    parent_level
+                };
+                spans[k] = Span {
+                    floor,
+                    level: high,
+                    offset: end as isize,
+                    reversed: false,
+                };
+                if k > depth {
+                    high = floor;
+                    end = order[end];
+                }
+            }
+            for k in depth..depth + count {
+                let parent = spans[k - 1];
+                let span = &mut spans[k];
+                let end = span.offset as usize;
+                let reversals = span
+                    .level
+                    .saturating_sub(span.floor.max(lowest_odd_level - 1));
+                span.offset = parent.offset;
+                span.reversed = parent.reversed;
+                if reversals & 1 != 0 {
+                    let mirror = (i + end - 1) as isize;
+                    span.offset += if parent.reversed { -mirror } else { mirror };
+                    span.reversed = !parent.reversed;
+                }
+            }
+            depth += count;
+        }
+        let span = spans[depth - 1];
+        order[i] = if span.reversed {
+            (span.offset - i as isize) as usize
+        } else {
+            (span.offset + i as isize) as usize
+        };
+    }
+}
+
+/// Computes an ordering by reversing the positions once per level (L2).
+#[cfg(not(feature = "alloc"))]
+fn reverse_by_level(
+    order: &mut [usize],
+    levels: impl Fn(usize) -> Level,
+    lowest_odd_level: Level,
+    max_level: Level,
+) {
+    let len = order.len();
+    for (i, pos) in order.iter_mut().enumerate() {
         *pos = i;
     }
     for level in (lowest_odd_level..=max_level).rev() {
@@ -917,13 +2944,7 @@ pub fn reorder(order: &mut [usize], levels: impl Fn(usize) -> Level) {
                 while end < len && levels(end) >= level {
                     end += 1;
                 }
-                let mut j = i;
-                let mut k = end - 1;
-                while j < k {
-                    order.swap(j, k);
-                    j += 1;
-                    k -= 1;
-                }
+                order[i..end].reverse();
                 i = end;
             }
             i += 1;
@@ -931,6 +2952,180 @@ pub fn reorder(order: &mut [usize], levels: impl Fn(usize) -> Level) {
     }
 }
 
+/// Run of text at a single level in visual order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VisualRun {
+    /// Level of the run.
+    pub level: Level,
+    /// Range of the run in the source text.
+    pub range: Range<usize>,
+}
+
+impl VisualRun {
+    /// Returns the direction of the run: `L` or `R`.
+    pub fn direction(&self) -> Type {
+        class_from_level(self.level)
+    }
+
+    /// Returns true if the content of the run is displayed from right to
+    /// left.
+    pub fn is_rtl(&self) -> bool {
+        self.level & 1 != 0
+    }
+}
+
+/// Computes the visual order of a sequence of level ranges, such as those
+/// produced by [`Resolved::to_ranges`], and returns an iterator over the
+/// runs in that order.
+///
+/// Rule L2 is applied to the ranges as a whole, so the cost depends only on
+/// the number of ranges. The indices of the ranges in visual order are
+/// stored in the first `ranges.len()` entries of `order`, which must be at
+/// least as long as `ranges`.
+pub fn reorder_ranges<'a>(ranges: &'a [LevelRange], order: &'a mut [usize]) -> VisualRuns<'a> {
+    let order = &mut order[..ranges.len()];
+    reorder(order, |i| ranges[i].0);
+    VisualRuns {
+        ranges,
+        order: order.iter(),
+    }
+}
+
+/// Iterator over the runs in visual order returned by [`reorder_ranges`].
+#[derive(Clone)]
+pub struct VisualRuns<'a> {
+    ranges: &'a [LevelRange],
+    order: core::slice::Iter<'a, usize>,
+}
+
+impl<'a> Iterator for VisualRuns<'a> {
+    type Item = VisualRun;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (level, range) = &self.ranges[*self.order.next()?];
+        Some(VisualRun {
+            level: *level,
+            range: range.clone(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl ExactSizeIterator for VisualRuns<'_> {}
+
+/// Item, such as a shaped glyph, that can be arranged in visual order by
+/// [`reorder_clusters`].
+pub trait Cluster {
+    /// Returns the level of the item.
+    fn level(&self) -> Level;
+
+    /// Returns the range of source text covered by the cluster that contains
+    /// the item. Consecutive items with the same range belong to a single
+    /// cluster and should have the same level.
+    fn source_range(&self) -> Range<usize>;
+}
+
+/// Computes the visual order of a sequence of items such as shaped glyphs
+/// and returns an iterator over the runs in that order.
+///
+/// Clusters are reordered according to rule L2 while the items inside each
+/// cluster keep their original order. The indices of the items in visual
+/// order are stored in the first `items.len()` entries of `order`, which
+/// must be at least as long as `items`.
+pub fn reorder_clusters<'a, T: Cluster>(
+    items: &'a [T],
+    order: &'a mut [usize],
+) -> ClusterRuns<'a, T> {
+    let order = &mut order[..items.len()];
+    reorder(order, |i| items[i].level());
+    // Items of a cluster in a reversed run now appear in descending order,
+    // so restore them.
+    let mut start = 0;
+    while start < order.len() {
+        let range = items[order[start]].source_range();
+        let mut end = start + 1;
+        while end < order.len()
+            && order[end] + 1 == order[end - 1]
+            && items[order[end]].source_range() == range
+        {
+            end += 1;
+        }
+        order[start..end].reverse();
+        start = end;
+    }
+    ClusterRuns {
+        items,
+        order: &order[..],
+    }
+}
+
+/// Run of items at a single level in visual order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClusterRun<'a> {
+    /// Level of the run.
+    pub level: Level,
+    /// Range of source text covered by the run.
+    pub source_range: Range<usize>,
+    /// Indices of the items in the run in visual order.
+    pub items: &'a [usize],
+}
+
+impl ClusterRun<'_> {
+    /// Returns the direction of the run: `L` or `R`.
+    pub fn direction(&self) -> Type {
+        class_from_level(self.level)
+    }
+
+    /// Returns true if the clusters of the run are displayed from right to
+    /// left.
+    pub fn is_rtl(&self) -> bool {
+        self.level & 1 != 0
+    }
+
+    /// Returns true if characters with the `Bidi_Mirrored` property in the
+    /// run must be displayed with mirrored glyphs (L4).
+    pub fn needs_mirroring(&self) -> bool {
+        self.is_rtl()
+    }
+}
+
+/// Iterator over the runs in visual order returned by [`reorder_clusters`].
+pub struct ClusterRuns<'a, T> {
+    items: &'a [T],
+    order: &'a [usize],
+}
+
+impl<'a, T: Cluster> Iterator for ClusterRuns<'a, T> {
+    type Item = ClusterRun<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = &self.items[*self.order.first()?];
+        let level = first.level();
+        let mut source_range = first.source_range();
+        let mut len = 1;
+        for &index in &self.order[1..] {
+            let item = &self.items[index];
+            if item.level() != level {
+                break;
+            }
+            let range = item.source_range();
+            source_range.start = source_range.start.min(range.start);
+            source_range.end = source_range.end.max(range.end);
+            len += 1;
+        }
+        let (items, rest) = self.order.split_at(len);
+        self.order = rest;
+        Some(ClusterRun {
+            level,
+            source_range,
+            items,
+        })
+    }
+}
+
 const OVERRIDE_MASK: u32 = RLE.mask() | LRE.mask() | RLO.mask() | LRO.mask();
 const ISOLATE_MASK: u32 = RLI.mask() | LRI.mask() | FSI.mask();
 const EXPLICIT_MASK: u32 = OVERRIDE_MASK | ISOLATE_MASK;
@@ -969,7 +3164,7 @@ fn find_limit_by_mask(types: &[Type], offset: usize, mask: u32) -> usize {
     len
 }
 
-#[derive(Clone)]
+#[derive(Copy, Clone)]
 struct Run {
     level: u8,
     ends_with_isolate: bool,
@@ -983,6 +3178,18 @@ struct Run {
 }
 
 impl Run {
+    const EMPTY: Self = Self {
+        level: 0,
+        ends_with_isolate: false,
+        starts_with_pdi: false,
+        sos: ON,
+        eos: ON,
+        start: 0,
+        end: 0,
+        in_sequence: false,
+        next: None,
+    };
+
     fn new(level: u8, start: usize, end: usize) -> Self {
         Self {
             level,
@@ -1047,33 +3254,29 @@ impl Stack {
 const MAX_BRACKET_STACK: usize = 63;
 
 struct BracketStack {
-    openers: [(usize, char); MAX_BRACKET_STACK],
+    openers: [(usize, u32); MAX_BRACKET_STACK],
     depth: usize,
 }
 
 impl BracketStack {
     fn new() -> Self {
         Self {
-            openers: [(0, '\0'); MAX_BRACKET_STACK],
+            openers: [(0, 0); MAX_BRACKET_STACK],
             depth: 0,
         }
     }
 
-    fn push(&mut self, offset: usize, closer: char) {
-        self.openers[self.depth] = (offset, closer);
+    fn push(&mut self, offset: usize, id: u32) {
+        self.openers[self.depth] = (offset, id);
         self.depth += 1;
     }
 
-    fn find_and_pop(&mut self, closer: char) -> Option<usize> {
+    fn find_and_pop(&mut self, id: u32) -> Option<usize> {
         if self.depth == 0 {
             return None;
         }
         for i in (0..self.depth).rev() {
-            let c = self.openers[i].1;
-            if c == closer
-                || (c == '\u{232A}' && closer == '\u{3009}')
-                || (c == '\u{3009}' && closer == '\u{232A}')
-            {
+            if self.openers[i].1 == id {
                 self.depth = i;
                 return Some(self.openers[i].0);
             }
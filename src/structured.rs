@@ -0,0 +1,240 @@
+//! Display of structured text such as URLs, file paths and expressions.
+//!
+//! The bidirectional algorithm has no notion of the syntax of structured
+//! text, so a path like `C:\<hebrew>\<hebrew>\file.txt` can be displayed
+//! with its components out of order. Similar to the structured text
+//! processors of Eclipse, the functions in this module split the text into
+//! tokens at the separators of its syntax and isolate each token in the
+//! direction of the syntax, so that the tokens and separators are always
+//! laid out in that direction while the text of each token is displayed as
+//! usual.
+
+use super::state::State;
+use super::{Level, Resolved, Type};
+use alloc::string::String;
+use core::ops::Range;
+
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const PDI: char = '\u{2069}';
+
+/// Syntax of a kind of structured text.
+pub trait Syntax {
+    /// Returns the direction in which the tokens and separators are laid
+    /// out, either `Type::L` or `Type::R`. Defaults to `Type::L`.
+    fn direction(&self) -> Type {
+        Type::L
+    }
+
+    /// Returns the length in bytes of the token at the start of the text
+    /// and of the separators that follow it. Either may be zero, but not
+    /// both unless the text is empty.
+    fn split(&self, text: &str) -> (usize, usize);
+}
+
+/// Syntax that separates tokens with any of a set of characters.
+///
+/// This is the basis for the built in syntaxes other than [`Expression`]
+/// and can be used to define others.
+#[derive(Copy, Clone, Debug)]
+pub struct Separators<'a> {
+    separators: &'a str,
+    direction: Type,
+}
+
+impl<'a> Separators<'a> {
+    /// Creates a left-to-right syntax with the specified separators.
+    pub const fn new(separators: &'a str) -> Self {
+        Self {
+            separators,
+            direction: Type::L,
+        }
+    }
+
+    /// Returns the syntax with the specified direction, either `Type::L` or
+    /// `Type::R`.
+    pub const fn with_direction(self, direction: Type) -> Self {
+        Self {
+            separators: self.separators,
+            direction,
+        }
+    }
+}
+
+impl Syntax for Separators<'_> {
+    fn direction(&self) -> Type {
+        self.direction
+    }
+
+    fn split(&self, text: &str) -> (usize, usize) {
+        let is_separator = |ch| self.separators.contains(ch);
+        let token = text.find(is_separator).unwrap_or(text.len());
+        let rest = &text[token..];
+        let separators = rest.find(|ch| !is_separator(ch)).unwrap_or(rest.len());
+        (token, separators)
+    }
+}
+
+/// URLs, separated at `:`, `/`, `?`, `#`, `[`, `]`, `@`, `.`, `&` and `=`.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Url;
+
+impl Syntax for Url {
+    fn split(&self, text: &str) -> (usize, usize) {
+        Separators::new(":/?#[]@.&=").split(text)
+    }
+}
+
+/// File paths in both Windows and Unix syntax, separated at `\`, `/`, `:`
+/// and `.`.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FilePath;
+
+impl Syntax for FilePath {
+    fn split(&self, text: &str) -> (usize, usize) {
+        Separators::new("\\/:.").split(text)
+    }
+}
+
+/// Email addresses, optionally with a display name and angle brackets,
+/// separated at `<`, `>`, `@`, `.`, `,`, `;`, `:`, `"` and spaces.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Email;
+
+impl Syntax for Email {
+    fn split(&self, text: &str) -> (usize, usize) {
+        Separators::new("<>@.,;:\" ").split(text)
+    }
+}
+
+/// Simple expressions such as `key=value` pairs and SQL statements,
+/// separated at whitespace, operators and punctuation. Quoted string
+/// literals are kept whole as a single token, including their quotes.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Expression;
+
+impl Syntax for Expression {
+    fn split(&self, text: &str) -> (usize, usize) {
+        const SEPARATORS: Separators = Separators::new(" \t!%&()*+,-/:;<=>?^|[]{}");
+        let quote = match text.chars().next() {
+            Some(quote) if quote == '\'' || quote == '"' => quote,
+            _ => return SEPARATORS.split(text),
+        };
+        // The literal ends at the matching quote, or with the text if there
+        // is none. Doubled quotes are escaped quotes in SQL, so they simply
+        // close and reopen the literal.
+        let mut token = text.len();
+        let mut rest = &text[1..];
+        while let Some(end) = rest.find(quote) {
+            let next = &rest[end + 1..];
+            if next.starts_with(quote) {
+                rest = &next[1..];
+                continue;
+            }
+            token = text.len() - next.len();
+            break;
+        }
+        let (_, separators) = SEPARATORS.split(&text[token..]);
+        (token, separators)
+    }
+}
+
+/// Returns an iterator over the byte ranges of the tokens of the text.
+pub fn tokens<'a, S: Syntax + ?Sized>(text: &'a str, syntax: &'a S) -> Tokens<'a, S> {
+    Tokens {
+        text,
+        syntax,
+        pos: 0,
+    }
+}
+
+/// Iterator over the tokens of structured text.
+///
+/// This struct is created by [`tokens`].
+#[derive(Clone)]
+pub struct Tokens<'a, S: ?Sized> {
+    text: &'a str,
+    syntax: &'a S,
+    pos: usize,
+}
+
+impl<S: Syntax + ?Sized> Iterator for Tokens<'_, S> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.text.len() {
+            let (token, separators) = self.syntax.split(&self.text[self.pos..]);
+            let start = self.pos;
+            // Guard against syntaxes that make no progress.
+            self.pos += (token + separators).max(1);
+            if token != 0 {
+                return Some(start..start + token);
+            }
+        }
+        None
+    }
+}
+
+/// Returns the structured text with directional isolates inserted so that
+/// it is displayed according to its syntax, similar to `process` of the
+/// Eclipse `STextProcessor`.
+///
+/// The whole text and each of its tokens are isolated in the direction of
+/// the syntax. The result can be displayed by any implementation of the
+/// bidirectional algorithm that supports isolates.
+pub fn process<S: Syntax + ?Sized>(text: &str, syntax: &S) -> String {
+    chars(text, syntax).map(|(ch, _)| ch).collect()
+}
+
+/// Resolves the levels of the characters of structured text as if it were
+/// processed by [`process`], without modifying the text.
+///
+/// Detects the base level if not provided. As with [`process`], the base
+/// level only determines where the text as a whole is placed.
+pub fn resolve_levels<S: Syntax + ?Sized>(
+    state: &mut State,
+    text: &str,
+    syntax: &S,
+    base_level: Option<Level>,
+) -> Resolved<Level> {
+    let mut resolved = Resolved::default();
+    let options = state.options();
+    state.resolve(
+        chars(text, syntax).map(|(ch, _)| (ch, options.char_class(ch))),
+        base_level,
+        &mut resolved,
+    );
+    let mut inserted = chars(text, syntax).map(|(_, inserted)| inserted);
+    resolved
+        .levels
+        .retain(|_| !inserted.next().unwrap_or(false));
+    resolved
+}
+
+/// Returns the characters of the processed text, each with true if it is
+/// an inserted isolate.
+fn chars<'a, S: Syntax + ?Sized>(
+    text: &'a str,
+    syntax: &'a S,
+) -> impl Iterator<Item = (char, bool)> + 'a {
+    let isolate = if syntax.direction() == Type::R {
+        RLI
+    } else {
+        LRI
+    };
+    let mut pos = 0;
+    let inner = tokens(text, syntax).flat_map(move |range| {
+        let separators = text[pos..range.start].chars().map(|ch| (ch, false));
+        pos = range.end;
+        separators
+            .chain(Some((isolate, true)))
+            .chain(text[range].chars().map(|ch| (ch, false)))
+            .chain(Some((PDI, true)))
+    });
+    let last = tokens(text, syntax).last().map_or(0, |range| range.end);
+    Some((isolate, true))
+        .into_iter()
+        .chain(inner)
+        .chain(text[last..].chars().map(|ch| (ch, false)))
+        .chain(Some((PDI, true)))
+}
@@ -0,0 +1,176 @@
+//! Debug trace of intermediate resolution state.
+
+use super::state::Diagnostic;
+use super::{is_removed_by_x9, Level, Type};
+use core::fmt::{self, Write};
+
+/// Width of a single column in the tabular trace output.
+const COLUMN: usize = 5;
+
+/// Optional sink for the debug trace and for diagnostics. All methods are
+/// no-ops when the respective target is not set.
+///
+/// The output is modeled after the trace emitted by the UAX #9 reference
+/// implementation: each phase is introduced by a `Trace:` header naming the
+/// rules that were applied, followed by rows of per character values.
+pub(crate) struct Tracer<'a> {
+    out: Option<&'a mut dyn Write>,
+    result: fmt::Result,
+    diagnostics: Option<&'a mut dyn FnMut(Diagnostic)>,
+}
+
+impl<'a> Tracer<'a> {
+    /// Creates a disabled tracer.
+    pub fn none() -> Self {
+        Self {
+            out: None,
+            result: Ok(()),
+            diagnostics: None,
+        }
+    }
+
+    /// Creates a tracer that writes to the specified target.
+    pub fn new(out: &'a mut dyn Write) -> Self {
+        Self {
+            out: Some(out),
+            result: Ok(()),
+            diagnostics: None,
+        }
+    }
+
+    /// Creates a tracer that only reports diagnostics to the specified
+    /// callback.
+    pub fn diagnostics(diagnostics: &'a mut dyn FnMut(Diagnostic)) -> Self {
+        Self {
+            out: None,
+            result: Ok(()),
+            diagnostics: Some(diagnostics),
+        }
+    }
+
+    /// Reports a diagnostic.
+    pub fn diagnostic(&mut self, diagnostic: Diagnostic) {
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics(diagnostic);
+        }
+    }
+
+    /// Returns true if tracing is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Returns the first error reported by the target, if any.
+    #[cfg(feature = "alloc")]
+    pub fn finish(self) -> fmt::Result {
+        self.result
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut dyn Write) -> fmt::Result) {
+        if self.result.is_err() {
+            return;
+        }
+        if let Some(out) = self.out.as_mut() {
+            self.result = f(&mut **out);
+        }
+    }
+
+    /// Begins the trace for a new phase.
+    pub fn header(&mut self, title: &str, rules: &str) {
+        self.write(|out| writeln!(out, "Trace: {} [{}]", title, rules));
+    }
+
+    /// Begins the row of input code points.
+    pub fn begin_text(&mut self) {
+        self.write(|out| write!(out, "  {:<12}", "Text:"));
+    }
+
+    /// Adds a code point to the row of input text.
+    pub fn text(&mut self, ch: char) {
+        self.write(|out| {
+            let cp = ch as u32;
+            write!(out, "{:04X}", cp)?;
+            let digits = if cp > 0xFFFFF {
+                6
+            } else if cp > 0xFFFF {
+                5
+            } else {
+                4
+            };
+            for _ in digits..COLUMN {
+                out.write_char(' ')?;
+            }
+            Ok(())
+        });
+    }
+
+    /// Ends the current row.
+    pub fn end_row(&mut self) {
+        self.write(|out| writeln!(out));
+    }
+
+    /// Writes the resolved paragraph embedding level.
+    pub fn paragraph_level(&mut self, level: Level) {
+        self.write(|out| writeln!(out, "  {:<12}{}", "Para_Level:", level));
+    }
+
+    /// Writes a row of bidirectional types for every character.
+    pub fn types(&mut self, label: &str, types: &[Type]) {
+        self.write(|out| {
+            write!(out, "  {:<12}", label)?;
+            for ty in types {
+                write!(out, "{:<w$}", ty, w = COLUMN)?;
+            }
+            writeln!(out)
+        });
+    }
+
+    /// Writes a row of levels for every character, marking those removed
+    /// by rule X9 with `x`.
+    pub fn levels(&mut self, classes: &[Type], levels: impl IntoIterator<Item = Level>) {
+        self.write(|out| {
+            write!(out, "  {:<12}", "Levels:")?;
+            for (ty, level) in classes.iter().zip(levels) {
+                if is_removed_by_x9(*ty) {
+                    write!(out, "{:<w$}", "x", w = COLUMN)?;
+                } else {
+                    write!(out, "{:<w$}", level, w = COLUMN)?;
+                }
+            }
+            writeln!(out)
+        });
+    }
+
+    /// Writes the summary line for an isolating run sequence.
+    pub fn sequence(&mut self, index: usize, level: Level, sos: Type, eos: Type) {
+        self.write(|out| {
+            writeln!(
+                out,
+                "  Sequence {}: level={} sos={} eos={}",
+                index, level, sos, eos
+            )
+        });
+    }
+
+    /// Writes a range belonging to the current isolating run sequence.
+    pub fn sequence_run(&mut self, start: usize, end: usize) {
+        self.write(|out| writeln!(out, "    Run:        {}..{}", start, end));
+    }
+
+    /// Writes the positions and current types of the characters in an
+    /// isolating run sequence.
+    pub fn sequence_types(&mut self, indices: &[usize], types: &[Type]) {
+        self.write(|out| {
+            write!(out, "    {:<12}", "Position:")?;
+            for index in indices {
+                write!(out, "{:<w$}", index, w = COLUMN)?;
+            }
+            writeln!(out)?;
+            write!(out, "    {:<12}", "Bidi_Class:")?;
+            for ty in types {
+                write!(out, "{:<w$}", ty, w = COLUMN)?;
+            }
+            writeln!(out)
+        });
+    }
+}
@@ -0,0 +1,365 @@
+//! Writing text in visual order.
+
+use super::state::{self, Options, State};
+use super::{reorder_ranges, Granularity, Level, LevelRange, Resolved, Type};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// Options for writing text in visual order.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct WriteOptions {
+    /// Removes the bidirectional control characters: the explicit
+    /// formatting characters and the implicit marks LRM, RLM and ALM.
+    pub remove_controls: bool,
+    /// Replaces characters at odd levels with their mirrored counterparts
+    /// (L4), for targets that do not mirror glyphs themselves.
+    pub mirror: bool,
+    /// Keeps nonspacing marks after their base characters in right-to-left
+    /// runs instead of reversing them along with the rest of the run.
+    pub keep_combining: bool,
+    /// Inserts LRM marks so that the output is displayed in the same order
+    /// when it is resolved again with a left-to-right base level, for
+    /// targets that apply the bidirectional algorithm themselves.
+    ///
+    /// This is similar to `OPTION_INSERT_MARKS` in ICU. A mark is written
+    /// between characters that would otherwise be displayed right to left
+    /// as part of the same run, such as two Hebrew letters or a Hebrew
+    /// letter and a number. Nonspacing marks are not separated from their
+    /// base characters, so `keep_combining` should also be set. Explicit
+    /// formatting characters in the text are not accounted for and should
+    /// be removed with `remove_controls`.
+    pub insert_marks: bool,
+}
+
+/// Writes a line of text in visual order using its resolved levels.
+///
+/// The levels must have been resolved for the line, either per character or
+/// per byte as specified by the granularity of `resolved`. This is similar
+/// to `writeReordered` in ICU and is intended for targets without a shaping
+/// engine, such as terminals. Characters are classified with the built in
+/// data; [`state::write_reordered`] uses the data of a state.
+///
+/// Returns an error without writing anything if the number of levels does
+/// not match the length of the text at that granularity.
+pub fn write_reordered(
+    text: &str,
+    resolved: &Resolved<Level>,
+    options: WriteOptions,
+    out: &mut impl Write,
+) -> fmt::Result {
+    write_reordered_with(Options::default(), text, resolved, options, out)
+}
+
+/// Writes a line of text in visual order, taking the character data from
+/// the options of a state.
+pub(crate) fn write_reordered_with(
+    data: Options,
+    text: &str,
+    resolved: &Resolved<Level>,
+    options: WriteOptions,
+    out: &mut impl Write,
+) -> fmt::Result {
+    if options.insert_marks {
+        let options = WriteOptions {
+            insert_marks: false,
+            ..options
+        };
+        let mut out = VisualMarks {
+            out,
+            data,
+            strong: Type::L,
+            raised: false,
+        };
+        write_visual(data, text, resolved, options, false, &mut out)
+    } else {
+        write_visual(data, text, resolved, options, false, out)
+    }
+}
+
+/// Writer that inserts LRM marks into text in visual order, so that
+/// resolving it with a left-to-right base level leaves every character
+/// that is displayed right to left in a run of its own.
+struct VisualMarks<'a, W> {
+    out: &'a mut W,
+    data: Options<'a>,
+    /// Last strong type, which determines the resolution of European
+    /// numbers (W2, W7).
+    strong: Type,
+    /// True if the last character that is not neutral would be resolved to
+    /// a level above the base level.
+    raised: bool,
+}
+
+impl<W: Write> Write for VisualMarks<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            self.write_char(ch)?;
+        }
+        Ok(())
+    }
+
+    fn write_char(&mut self, ch: char) -> fmt::Result {
+        let ty = self.data.char_class(ch);
+        let mut raised = match ty {
+            Type::L => false,
+            Type::R | Type::AL | Type::AN => true,
+            Type::EN => self.strong != Type::L,
+            // Neutral characters between two runs are resolved to the base
+            // level once the runs are separated, and nonspacing marks follow
+            // their base characters.
+            _ => return self.out.write_char(ch),
+        };
+        if raised && self.raised {
+            // European numbers that follow the mark are left to right.
+            self.out.write_char(LRM)?;
+            self.strong = Type::L;
+            raised = ty != Type::EN;
+        }
+        if ty == Type::L || ty == Type::R || ty == Type::AL {
+            self.strong = ty;
+        }
+        self.raised = raised;
+        self.out.write_char(ch)
+    }
+}
+
+/// Writes text in visual order. When `options.insert_marks` is set, marks
+/// are inserted at the boundaries of runs so that resolving the output with
+/// the same base level yields the original levels and reordering it
+/// restores the original order. When `numbers_as_l` is also set, marks are
+/// inserted between numbers and adjacent neutral characters in
+/// left-to-right runs, since resolving the output would otherwise treat
+/// them as right-to-left context.
+fn write_visual(
+    data: Options,
+    text: &str,
+    resolved: &Resolved<Level>,
+    options: WriteOptions,
+    numbers_as_l: bool,
+    out: &mut impl Write,
+) -> fmt::Result {
+    let ranges = level_ranges(text, resolved)?;
+    let mut order = alloc::vec![0; ranges.len()];
+    // Mark written at the end of the previous run, which also serves as the
+    // mark at the start of the next one.
+    let mut last_mark = None;
+    for run in reorder_ranges(&ranges, &mut order) {
+        let rtl = run.is_rtl();
+        let mark = run_mark(run.level, resolved.base_level);
+        let text = &text[run.range];
+        let (first, last) = if rtl {
+            (text.chars().next_back(), text.chars().next())
+        } else {
+            (text.chars().next(), text.chars().next_back())
+        };
+        if options.insert_marks && !is_strong(data, first, mark) && last_mark != Some(mark) {
+            out.write_char(mark)?;
+        }
+        last_mark = None;
+        if rtl {
+            write_rtl_run(data, text, options, out)?;
+        } else {
+            let mark_numbers = options.insert_marks && numbers_as_l;
+            write_ltr_run(data, text, options, mark_numbers, out)?;
+        }
+        if options.insert_marks && !is_strong(data, last, mark) {
+            out.write_char(mark)?;
+            last_mark = Some(mark);
+        }
+    }
+    Ok(())
+}
+
+/// Algorithms for converting visually ordered text to logical order.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InverseMode {
+    /// Applies the bidirectional algorithm to the visual text as if it were
+    /// in logical order, like `REORDER_INVERSE_LIKE_DIRECT` in ICU.
+    LikeDirect,
+    /// Like `LikeDirect`, but treats numbers as left-to-right characters,
+    /// like `REORDER_INVERSE_NUMBERS_AS_L` in ICU. This keeps digits in
+    /// place for data that was stored with numbers already reversed.
+    NumbersAsL,
+}
+
+/// Writes visually ordered text, such as legacy Hebrew or Arabic data, in
+/// logical order.
+///
+/// LRM and RLM marks are inserted where needed so that resolving the output
+/// with the same base level and writing it in visual order reproduces the
+/// input. The base level is detected from the visual text if not provided.
+/// Characters are classified with the built in data;
+/// [`state::write_logical`] uses the data of a state.
+///
+/// Marks can only reproduce levels up to two above the base level, so text
+/// containing explicit formatting characters may not round trip. Neither may
+/// some sequences of mixed European and Arabic numbers or brackets, whose
+/// resolution depends on more than the surrounding direction.
+pub fn write_logical(
+    visual: &str,
+    base_level: Option<Level>,
+    mode: InverseMode,
+    out: &mut impl Write,
+) -> fmt::Result {
+    state::write_logical(&mut State::new(), visual, base_level, mode, out)
+}
+
+/// Writes visually ordered text in logical order with a state that is set
+/// to the reordering mode for the inverse mode.
+pub(crate) fn write_logical_with(
+    state: &mut State,
+    visual: &str,
+    base_level: Option<Level>,
+    mode: InverseMode,
+    out: &mut impl Write,
+) -> fmt::Result {
+    let mut resolved = Resolved::default();
+    state::resolve_levels_into(state, visual, Granularity::Char, base_level, &mut resolved);
+    let options = WriteOptions {
+        insert_marks: true,
+        ..Default::default()
+    };
+    let numbers_as_l = mode == InverseMode::NumbersAsL;
+    write_visual(
+        state.options(),
+        visual,
+        &resolved,
+        options,
+        numbers_as_l,
+        out,
+    )
+}
+
+pub(crate) const LRM: char = '\u{200E}';
+pub(crate) const RLM: char = '\u{200F}';
+
+/// Returns the mark that establishes the level of a run when the visual
+/// output is resolved again.
+pub(crate) fn run_mark(level: Level, base_level: Level) -> char {
+    if level & 1 != 0 {
+        RLM
+    } else if base_level & 1 == 0 && level == base_level + 2 {
+        // Only numbers following right-to-left text are raised by two levels
+        // above an even base level, so they must stay in that context.
+        RLM
+    } else {
+        LRM
+    }
+}
+
+/// Returns true if the character at the boundary of a run is a strong type
+/// in the same direction as the mark.
+pub(crate) fn is_strong(data: Options, ch: Option<char>, mark: char) -> bool {
+    let ty = match ch {
+        Some(ch) => data.char_class(ch),
+        None => return false,
+    };
+    if mark == LRM {
+        ty == Type::L
+    } else {
+        ty == Type::R || ty == Type::AL
+    }
+}
+
+fn write_ltr_run(
+    data: Options,
+    text: &str,
+    options: WriteOptions,
+    mark_numbers: bool,
+    out: &mut impl Write,
+) -> fmt::Result {
+    let mut prev = Type::L;
+    for ch in text.chars() {
+        let ty = data.char_class(ch);
+        if mark_numbers && ty != Type::NSM && is_number(prev) != is_number(ty) {
+            let neutral = if is_number(prev) { ty } else { prev };
+            if neutral != Type::L {
+                out.write_char(LRM)?;
+            }
+        }
+        if ty != Type::NSM {
+            prev = ty;
+        }
+        write_char(data, ch, false, options, out)?;
+    }
+    Ok(())
+}
+
+fn is_number(ty: Type) -> bool {
+    ty == Type::EN || ty == Type::AN
+}
+
+fn write_rtl_run(
+    data: Options,
+    text: &str,
+    options: WriteOptions,
+    out: &mut impl Write,
+) -> fmt::Result {
+    // Marks that follow a base character are written after it once the base
+    // is reached.
+    let mut marks_end = text.len();
+    for (i, ch) in text.char_indices().rev() {
+        if options.keep_combining && data.char_class(ch) == Type::NSM {
+            continue;
+        }
+        write_char(data, ch, true, options, out)?;
+        for ch in text[i + ch.len_utf8()..marks_end].chars() {
+            write_char(data, ch, true, options, out)?;
+        }
+        marks_end = i;
+    }
+    for ch in text[..marks_end].chars() {
+        write_char(data, ch, true, options, out)?;
+    }
+    Ok(())
+}
+
+/// Returns the byte ranges of the text at each level, or an error if the
+/// number of levels does not match the length of the text.
+fn level_ranges(text: &str, resolved: &Resolved<Level>) -> Result<Vec<LevelRange>, fmt::Error> {
+    let len = match resolved.granularity {
+        Granularity::Char => text.chars().count(),
+        Granularity::Byte => text.len(),
+    };
+    if resolved.levels.len() != len {
+        return Err(fmt::Error);
+    }
+    let mut ranges: Vec<LevelRange> = Vec::new();
+    for (index, (offset, ch)) in text.char_indices().enumerate() {
+        let level = match resolved.granularity {
+            Granularity::Char => resolved.levels[index],
+            Granularity::Byte => resolved.levels[offset],
+        };
+        let end = offset + ch.len_utf8();
+        match ranges.last_mut() {
+            Some((last, range)) if *last == level => range.end = end,
+            _ => ranges.push((level, offset..end)),
+        }
+    }
+    Ok(ranges)
+}
+
+fn write_char(
+    data: Options,
+    ch: char,
+    rtl: bool,
+    options: WriteOptions,
+    out: &mut impl Write,
+) -> fmt::Result {
+    if options.remove_controls && is_bidi_control(ch) {
+        return Ok(());
+    }
+    if options.mirror && rtl {
+        return out.write_char(data.char_mirror(ch).unwrap_or(ch));
+    }
+    out.write_char(ch)
+}
+
+/// Returns true if the character is an explicit directional formatting
+/// character or an implicit directional mark.
+fn is_bidi_control(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{61C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
@@ -0,0 +1,104 @@
+use bidi::state::State;
+use bidi::*;
+
+#[test]
+fn unicode_version() {
+    // ARABIC END OF TEXT MARK and the new brackets were added in 14.0.
+    let (ty, mirrored) = if UNICODE_VERSION >= (14, 0, 0) {
+        (Type::AL, Some('\u{2E56}'))
+    } else {
+        (Type::L, None)
+    };
+    assert_eq!(Type::from_char('\u{61D}'), ty);
+    assert_eq!(mirror('\u{2E55}'), mirrored);
+    assert_eq!(Type::from_char('\u{5D0}'), Type::R);
+    assert_eq!(mirror('('), Some(')'));
+}
+
+#[test]
+fn bracket_pairs_out_of_order() {
+    // The closing brackets of these pairs are in the opposite order of
+    // their opening brackets.
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    for text in ["\u{5D0}\u{298D}a\u{2990}", "\u{5D0}\u{298F}a\u{298E}"] {
+        state.resolve(
+            text.chars().map(|ch| (ch, Type::from_char(ch))),
+            None,
+            &mut resolved,
+        );
+        assert_eq!(state.bracket_pairs().len(), 1);
+    }
+}
+
+#[test]
+fn builtin_data_blob() {
+    let bytes = DataBlob::encode_builtin();
+    let data = DataBlob::new(&bytes).unwrap();
+    assert_eq!(data.unicode_version(), UNICODE_VERSION);
+    for ch in (0..0x20000).step_by(7).filter_map(char::from_u32) {
+        assert_eq!(data.bidi_class(ch), Type::from_char(ch));
+        assert_eq!(data.mirror(ch), mirror(ch));
+    }
+    assert_eq!(
+        DataBlob::new(&bytes[..bytes.len() - 1]).unwrap_err(),
+        DataError::Length
+    );
+    let mut corrupt = bytes.clone();
+    corrupt[0] = b'X';
+    assert_eq!(DataBlob::new(&corrupt).unwrap_err(), DataError::Header);
+    let mut corrupt = bytes.clone();
+    corrupt[20..23].copy_from_slice(&[0xFF, 0xFF, 0x10]);
+    assert_eq!(DataBlob::new(&corrupt).unwrap_err(), DataError::Classes);
+}
+
+#[test]
+fn state_with_data_blob() {
+    // Private use characters are right-to-left and there are no brackets.
+    let mut bytes = b"BIDI\x01\x0E\x00\x00".to_vec();
+    bytes.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    bytes.extend_from_slice(&[0x00, 0xE0, 0x00, 0x00, 0xF9, 0x00, Type::R.0]);
+    let mut state = State::new();
+    state.set_data(Some(DataBlob::new(&bytes).unwrap()));
+    let resolved = state::resolve_levels(&mut state, "a\u{E000}(b)", Granularity::Char, Some(0));
+    assert_eq!(resolved.levels, [0, 1, 0, 0, 0]);
+    assert!(state.bracket_pairs().is_empty());
+    state.set_data(None);
+    let resolved = state::resolve_levels(&mut state, "a\u{E000}(b)", Granularity::Char, Some(0));
+    assert_eq!(resolved.levels, [0; 5]);
+}
+
+struct Icons;
+
+impl Provider for Icons {
+    fn bidi_class(&self, ch: char) -> Type {
+        match ch {
+            '\u{E000}' => Type::R,
+            '\u{E001}'..='\u{F8FF}' => Type::ON,
+            _ => BuiltinProvider.bidi_class(ch),
+        }
+    }
+
+    fn bracket_type(&self, ch: char) -> BracketType {
+        match ch {
+            '<' => BracketType::Open('>'),
+            '>' => BracketType::Close('<'),
+            _ => BuiltinProvider.bracket_type(ch),
+        }
+    }
+}
+
+#[test]
+fn state_with_provider() {
+    let icons = Icons;
+    let mut state = State::new();
+    state.set_provider(Some(&icons));
+    let resolved =
+        state::resolve_levels(&mut state, "a\u{E000}\u{E001}", Granularity::Char, Some(0));
+    assert_eq!(resolved.levels, [0, 1, 0]);
+    let resolved = state::resolve_levels(&mut state, "\u{5D0}<a>(b)", Granularity::Char, None);
+    assert_eq!(resolved.levels, [1, 1, 2, 1, 1, 2, 1]);
+    assert_eq!(state.bracket_pairs().len(), 2);
+    assert_eq!(BracketType::from_char('('), BracketType::Open(')'));
+    assert_eq!(BracketType::from_char('<'), BracketType::None);
+}
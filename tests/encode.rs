@@ -0,0 +1,97 @@
+use bidi::*;
+
+fn is_control(ch: char) -> bool {
+    matches!(ch, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Resolves the encoded text and returns the levels of the characters that
+/// were not inserted.
+fn decode(encoded: &str, base_level: Level) -> Vec<Level> {
+    let resolved = resolve_levels(encoded, Granularity::Char, Some(base_level));
+    encoded
+        .chars()
+        .zip(resolved.levels)
+        .filter(|(ch, _)| !is_control(*ch))
+        .map(|(_, level)| level)
+        .collect()
+}
+
+#[test]
+fn encode_levels_round_trip() {
+    for (text, levels, base_level) in &[
+        ("abc", &[0, 0, 0][..], 0),
+        ("abc \u{5D0}", &[0, 0, 0, 0, 1], 0),
+        ("abc def", &[0, 0, 0, 0, 1, 1, 1], 0),
+        ("a\u{5D0}12b", &[0, 3, 4, 4, 2], 0),
+        ("x (y) z", &[2, 2, 3, 3, 3, 2, 2], 1),
+        ("\u{5D0} b \u{5D1}", &[1, 1, 2, 1, 1], 1),
+    ] {
+        let encoded = encode_levels(text, levels, *base_level).unwrap();
+        assert_eq!(decode(&encoded, *base_level), *levels);
+    }
+}
+
+#[test]
+fn encode_levels_bounds() {
+    // Text that already resolves to the levels is returned unchanged.
+    assert_eq!(
+        encode_levels("abc \u{5D0}", &[0, 0, 0, 0, 1], 0).unwrap(),
+        "abc \u{5D0}"
+    );
+    assert_eq!(
+        encode_levels("\u{5D0} 12", &[1, 1, 2, 2], 0).unwrap(),
+        "\u{5D0} 12"
+    );
+    // Marks are only inserted next to characters that are not strong in
+    // the direction of their run, at most one on each side of a run.
+    assert_eq!(
+        encode_levels("\u{5D0} 1", &[1, 0, 0], 0).unwrap(),
+        "\u{5D0}\u{200E} 1"
+    );
+    for (text, levels, base_level) in &[
+        ("\u{5D0} 1", &[1, 0, 0][..], 0),
+        ("a 1 \u{5D0} 2 b", &[0, 0, 0, 0, 1, 0, 0, 0, 0], 0),
+        ("a \u{5D0} b", &[0, 1, 1, 1, 0], 0),
+        ("\u{5D0}, a. \u{5D1}", &[1, 1, 1, 2, 2, 1, 1], 1),
+    ] {
+        let encoded = encode_levels(text, levels, *base_level).unwrap();
+        assert_eq!(decode(&encoded, *base_level), *levels);
+        let runs = 1 + levels.windows(2).filter(|w| w[0] != w[1]).count();
+        let chars = encoded.chars().collect::<Vec<_>>();
+        let is_mark = |ch: char| ch == '\u{200E}' || ch == '\u{200F}';
+        assert!(chars.iter().all(|&ch| !is_control(ch) || is_mark(ch)));
+        assert!(chars.iter().filter(|&&ch| is_mark(ch)).count() <= 2 * runs);
+        for (i, &ch) in chars.iter().enumerate().filter(|(_, &ch)| is_mark(ch)) {
+            // A strong type in the direction of the mark.
+            let strong = |other: Option<&char>| {
+                other
+                    .map(|other| Type::from_char(*other))
+                    .is_some_and(|ty| match ty {
+                        Type::L => ch == '\u{200E}',
+                        Type::R | Type::AL => ch == '\u{200F}',
+                        _ => false,
+                    })
+            };
+            assert!(!strong(i.checked_sub(1).map(|i| &chars[i])) || !strong(chars.get(i + 1)));
+        }
+    }
+    // Isolates and overrides are used when marks are not enough.
+    assert_eq!(
+        encode_levels("abc def", &[0, 0, 0, 0, 1, 1, 1], 0).unwrap(),
+        "abc \u{202E}def\u{202C}"
+    );
+}
+
+#[test]
+fn encode_levels_unreachable() {
+    // Strong right-to-left characters cannot be at an even level.
+    assert_eq!(encode_levels("\u{5D0}", &[0], 0), None);
+    // Segment separators are reset to the base level.
+    assert_eq!(encode_levels("a\t", &[1, 1], 0), None);
+    // Trailing whitespace is reset unless a strong mark follows it.
+    let encoded = encode_levels("a ", &[1, 1], 0).unwrap();
+    assert_eq!(decode(&encoded, 0), [1, 1]);
+    // Levels must be at or above the base level.
+    assert_eq!(encode_levels("a", &[0], 1), None);
+    assert_eq!(encode_levels("ab", &[0], 0), None);
+}
@@ -0,0 +1,53 @@
+use bidi::state::{CapacityError, FixedState};
+use bidi::*;
+
+#[test]
+fn fixed_state_matches_allocating_state() {
+    let mut state = FixedState::<32>::new();
+    for text in &[
+        "abc",
+        "\u{5D0}\u{5D1} (abc) 123",
+        "a\u{2067}\u{5D0} [1]\u{2069}b\u{202B}c\u{202C}",
+        "",
+    ] {
+        let mut levels = [0; 32];
+        let base_level = state
+            .resolve(
+                text.chars().map(|ch| (ch, Type::from_char(ch))),
+                None,
+                &mut levels,
+            )
+            .unwrap();
+        let expected = resolve_levels(text, Granularity::Char, None);
+        let len = expected.levels.len();
+        assert_eq!(base_level, expected.base_level);
+        assert_eq!(&levels[..len], &expected.levels[..]);
+    }
+}
+
+#[test]
+fn fixed_state_capacity() {
+    let mut state = FixedState::<4>::new();
+    let mut levels = [0; 8];
+    let text = "\u{5D0}bcde";
+    let result = state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        None,
+        &mut levels,
+    );
+    assert_eq!(result, Err(CapacityError));
+    let text = "\u{5D0}bc";
+    let result = state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        None,
+        &mut levels[..2],
+    );
+    assert_eq!(result, Err(CapacityError));
+    let result = state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        None,
+        &mut levels[..3],
+    );
+    assert_eq!(result, Ok(1));
+    assert_eq!(levels[..3], [1, 2, 2]);
+}
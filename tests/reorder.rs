@@ -0,0 +1,115 @@
+use bidi::*;
+
+const TEXTS: &[&str] = &[
+    "abc",
+    "\u{5D0}\u{5D1} (abc) 123",
+    "a\u{2067}\u{5D0} [1]\u{2069}b\u{202B}c\u{202C}",
+    "\u{627}\u{644} 12 abc \u{202A}de \u{5D0}\u{202C}f",
+    "",
+];
+
+#[test]
+fn visual_runs_match_character_order() {
+    for text in TEXTS {
+        let resolved = resolve_levels(text, Granularity::Char, None);
+        let mut expected = vec![0; resolved.levels.len()];
+        reorder(&mut expected, |i| resolved.levels[i]);
+        let mut order = Vec::new();
+        for run in resolved.to_ranges().visual_runs() {
+            assert_eq!(run.is_rtl(), run.direction() == Type::R);
+            if run.is_rtl() {
+                order.extend(run.range.rev());
+            } else {
+                order.extend(run.range);
+            }
+        }
+        assert_eq!(order, expected);
+    }
+}
+
+#[test]
+fn reorder_ranges_order() {
+    let ranges = [(0, 0..3), (1, 3..5), (2, 5..6), (1, 6..8), (0, 8..9)];
+    let mut order = [0; 8];
+    let runs = reorder_ranges(&ranges, &mut order).collect::<Vec<_>>();
+    assert_eq!(&order[..5], &[0, 3, 2, 1, 4]);
+    assert_eq!(
+        runs,
+        vec![
+            VisualRun {
+                level: 0,
+                range: 0..3
+            },
+            VisualRun {
+                level: 1,
+                range: 6..8
+            },
+            VisualRun {
+                level: 2,
+                range: 5..6
+            },
+            VisualRun {
+                level: 1,
+                range: 3..5
+            },
+            VisualRun {
+                level: 0,
+                range: 8..9
+            },
+        ]
+    );
+}
+
+struct Glyph {
+    level: Level,
+    cluster: std::ops::Range<usize>,
+}
+
+impl Cluster for Glyph {
+    fn level(&self) -> Level {
+        self.level
+    }
+
+    fn source_range(&self) -> std::ops::Range<usize> {
+        self.cluster.clone()
+    }
+}
+
+#[test]
+fn reorder_glyph_clusters() {
+    let glyphs = [
+        (0, 0..1),
+        (0, 1..2),
+        (1, 2..4),
+        (1, 2..4),
+        (1, 4..5),
+        (0, 5..6),
+    ]
+    .iter()
+    .map(|(level, cluster)| Glyph {
+        level: *level,
+        cluster: cluster.clone(),
+    })
+    .collect::<Vec<_>>();
+    let mut order = [0; 6];
+    let runs = reorder_clusters(&glyphs, &mut order)
+        .map(|run| {
+            (
+                run.level,
+                run.source_range.clone(),
+                run.items.to_vec(),
+                run.direction(),
+                run.needs_mirroring(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        runs,
+        vec![
+            (0, 0..2, vec![0, 1], Type::L, false),
+            (1, 2..5, vec![4, 2, 3], Type::R, true),
+            (0, 5..6, vec![5], Type::L, false),
+        ]
+    );
+    assert_eq!(order, [0, 1, 4, 2, 3, 5]);
+}
@@ -0,0 +1,42 @@
+use bidi::*;
+
+#[test]
+fn requires_bidi_covers_all_rtl_and_controls() {
+    for cp in 0..=0x10FFFF {
+        let ch = match core::char::from_u32(cp) {
+            Some(ch) => ch,
+            None => continue,
+        };
+        let needs_bidi = matches!(
+            Type::from_char(ch),
+            Type::R
+                | Type::AL
+                | Type::AN
+                | Type::LRE
+                | Type::RLE
+                | Type::LRO
+                | Type::RLO
+                | Type::PDF
+                | Type::LRI
+                | Type::RLI
+                | Type::FSI
+                | Type::PDI
+        );
+        if needs_bidi {
+            let mut text = String::from("padding text ");
+            text.push(ch);
+            assert!(requires_bidi(&text), "U+{:04X}", cp);
+        }
+    }
+}
+
+#[test]
+fn requires_bidi_ltr() {
+    assert!(!requires_bidi(""));
+    assert!(!requires_bidi(
+        "The quick brown fox jumps over the lazy dog 0123"
+    ));
+    assert!(!requires_bidi("Grüße, 日本語, ελληνικά, русский"));
+    assert!(requires_bidi("The quick brown fox \u{5D0}"));
+    assert!(requires_bidi("\u{2067}"));
+}
@@ -0,0 +1,370 @@
+use bidi::state::State;
+use bidi::*;
+
+fn resolve(state: &mut State, text: &str, base_level: Option<Level>) -> Resolved<Level> {
+    let mut resolved = Resolved::default();
+    state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        base_level,
+        &mut resolved,
+    );
+    resolved
+}
+
+type SequenceInfo = (Level, Type, Type, Vec<(usize, usize)>);
+
+fn sequences(state: &State) -> Vec<SequenceInfo> {
+    state
+        .sequences()
+        .map(|s| {
+            let ranges = s.ranges().map(|r| (r.start, r.end)).collect();
+            (s.level(), s.sos(), s.eos(), ranges)
+        })
+        .collect()
+}
+
+#[test]
+fn isolating_run_sequences() {
+    let mut state = State::new();
+    resolve(&mut state, "a\u{2067}\u{5D0}\u{2069}b", Some(0));
+    assert_eq!(
+        sequences(&state),
+        [
+            (0, Type::L, Type::L, vec![(0, 2), (3, 5)]),
+            (1, Type::R, Type::R, vec![(2, 3)]),
+        ]
+    );
+    resolve(&mut state, "abc", None);
+    assert_eq!(sequences(&state), [(0, Type::L, Type::L, vec![(0, 3)])]);
+}
+
+#[test]
+fn resolved_types() {
+    let mut state = State::new();
+    resolve(&mut state, "\u{5D0} 12 a\u{200B}", None);
+    let types = state.resolved_types().collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            Type::R,
+            Type::R,
+            Type::EN,
+            Type::EN,
+            Type::R,
+            Type::L,
+            Type::BN
+        ]
+    );
+    resolve(&mut state, "a 1", None);
+    let types = state.resolved_types().collect::<Vec<_>>();
+    assert_eq!(types, [Type::L, Type::L, Type::L]);
+}
+
+#[test]
+fn bracket_pairs() {
+    let mut state = State::new();
+    resolve(&mut state, "\u{5D0}(a[b]) \u{5D2}(\u{5D1}) ( ) ]", Some(0));
+    let pairs = state
+        .bracket_pairs()
+        .iter()
+        .map(|p| (p.open, p.close, p.direction))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pairs,
+        [
+            (1, 6, Type::L),
+            (3, 5, Type::L),
+            (9, 11, Type::R),
+            (13, 15, Type::ON)
+        ]
+    );
+}
+
+#[test]
+fn streamed_ranges() {
+    let mut state = State::new();
+    let text = "\u{5D0}a b\u{5D1}";
+    let mut ranges = Vec::new();
+    let base_level =
+        state::resolve_ranges_with(&mut state, text, Granularity::Byte, None, |level, range| {
+            ranges.push((level, range.start, range.end))
+        });
+    assert_eq!(base_level, 1);
+    assert_eq!(ranges, [(1, 0, 2), (2, 2, 5), (1, 5, 7)]);
+    let resolved = resolve_ranges(text, Granularity::Char, None);
+    assert_eq!(resolved.levels, [(1, 0..1), (2, 1..4), (1, 4..5)]);
+}
+
+#[test]
+fn retain_removed() {
+    let text = "\u{202B}a \u{AD}\t";
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        Some(0),
+        &mut resolved,
+    );
+    assert_eq!(resolved.levels, [0, 2, 0, 1, 0]);
+    assert_eq!(
+        state.removed().collect::<Vec<_>>(),
+        [true, false, false, true, false]
+    );
+    state.set_retain_removed(true);
+    state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        Some(0),
+        &mut resolved,
+    );
+    assert_eq!(resolved.levels, [0, 2, 0, 0, 0]);
+    // Initiators and PDFs take the level outside of their embedding.
+    let levels = |state: &mut State, text| resolve(state, text, Some(0)).levels;
+    assert_eq!(levels(&mut state, "\u{5D0}\u{202A}b"), [1, 0, 2]);
+    assert_eq!(
+        levels(&mut state, "\u{5D0}\u{202B}\u{5D1}\u{202C}c"),
+        [1, 0, 1, 0, 0]
+    );
+    // Removed characters are resolved along with adjacent numbers and
+    // neutrals.
+    assert_eq!(levels(&mut state, "a\u{5D0}\u{AD}1"), [0, 1, 2, 2]);
+    assert_eq!(
+        levels(&mut state, "\u{5D0}\u{AD} \u{5D1}a"),
+        [1, 1, 1, 1, 0]
+    );
+    assert_eq!(levels(&mut state, "\u{5D0}\u{AD}b"), [1, 0, 0]);
+    state.set_retain_removed(false);
+    assert_eq!(levels(&mut state, "\u{5D0}\u{202A}b"), [1, 1, 2]);
+    assert_eq!(levels(&mut state, "a\u{5D0}\u{AD}1"), [0, 1, 1, 2]);
+}
+
+#[test]
+fn reordering_modes() {
+    use bidi::state::ReorderingMode;
+    let mut state = State::new();
+    let levels = |state: &mut State, text| resolve(state, text, Some(0)).levels;
+    assert_eq!(levels(&mut state, "a 12\u{5D0}"), [0, 0, 0, 0, 1]);
+    assert_eq!(levels(&mut state, "a 12 \u{5D0}"), [0, 0, 0, 0, 0, 1]);
+    assert_eq!(levels(&mut state, "a \u{661} b"), [0, 0, 2, 0, 0]);
+    assert_eq!(levels(&mut state, "\u{5D0} 12"), [1, 1, 2, 2]);
+    state.set_reordering_mode(ReorderingMode::NumbersSpecial);
+    assert_eq!(levels(&mut state, "a 12\u{5D0}"), [0, 0, 2, 2, 1]);
+    assert_eq!(levels(&mut state, "a 12 \u{5D0}"), [0, 0, 0, 0, 0, 1]);
+    state.set_reordering_mode(ReorderingMode::GroupNumbersWithR);
+    assert_eq!(levels(&mut state, "a 12 \u{5D0}"), [0, 0, 2, 2, 1, 1]);
+    assert_eq!(levels(&mut state, "a 12 b"), [0; 6]);
+    assert_eq!(levels(&mut state, "a \u{661} b"), [0; 5]);
+    state.set_reordering_mode(ReorderingMode::InverseNumbersAsL);
+    assert_eq!(levels(&mut state, "\u{5D0} 12"), [1, 0, 0, 0]);
+    state.set_reordering_mode(ReorderingMode::RunsOnly);
+    let resolved = resolve(&mut state, "ab \u{5D0}\u{5D1} cd", Some(0));
+    assert_eq!(resolved.levels, [0, 0, 0, 1, 1, 0, 0, 0]);
+    let mut order = [0; 8];
+    state.reorder(&mut order, |i| resolved.levels[i]);
+    assert_eq!(order, [5, 6, 7, 3, 4, 0, 1, 2]);
+    state.set_reordering_mode(ReorderingMode::Default);
+    state.reorder(&mut order, |i| resolved.levels[i]);
+    assert_eq!(order, [0, 1, 2, 4, 3, 5, 6, 7]);
+}
+
+#[test]
+fn algorithm_version() {
+    use bidi::state::AlgorithmVersion;
+    let mut state = State::new();
+    let isolated = "a \u{2067}\u{5D0}\u{2069} 1";
+    let brackets = "\u{5D0}(a)";
+    assert_eq!(
+        resolve(&mut state, isolated, None).levels,
+        [0, 0, 0, 1, 0, 0, 0]
+    );
+    assert_eq!(resolve(&mut state, brackets, None).levels, [1, 1, 2, 1]);
+    assert_eq!(state.bracket_pairs().len(), 1);
+    state.set_algorithm_version(AlgorithmVersion::Unicode6_2);
+    assert_eq!(
+        resolve(&mut state, isolated, None).levels,
+        [0, 0, 0, 1, 1, 1, 2]
+    );
+    assert_eq!(
+        state.removed().collect::<Vec<_>>(),
+        [false, false, true, false, true, false, false]
+    );
+    assert_eq!(resolve(&mut state, brackets, None).levels, [1, 1, 2, 1]);
+    assert!(state.bracket_pairs().is_empty());
+    let nested = "\u{202A}".repeat(40) + "a";
+    assert_eq!(resolve(&mut state, &nested, Some(0)).levels[40], 60);
+    state.set_algorithm_version(AlgorithmVersion::Current);
+    assert_eq!(resolve(&mut state, &nested, Some(0)).levels[40], 80);
+}
+
+#[test]
+fn resolve_classes() {
+    use bidi::state::Bracket;
+    let items = |close| {
+        [
+            (Type::L, None),
+            (Type::ON, Some(Bracket::Open(7))),
+            (Type::R, None),
+            (Type::ON, Some(Bracket::Close(close))),
+            (Type::R, None),
+        ]
+    };
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    state.resolve_classes(items(7).iter().copied(), Some(0), &mut resolved);
+    assert_eq!(resolved.levels, [0, 0, 1, 0, 1]);
+    assert_eq!(state.bracket_pairs().len(), 1);
+    assert_eq!(
+        resolve(&mut state, "a(\u{5D0})\u{5D1}", Some(0)).levels,
+        resolved.levels
+    );
+    state.resolve_classes(items(8).iter().copied(), Some(0), &mut resolved);
+    assert_eq!(resolved.levels, [0, 0, 1, 1, 1]);
+    assert!(state.bracket_pairs().is_empty());
+}
+
+#[test]
+fn resolve_with_context() {
+    let mut state = State::new();
+    let cases = [
+        ("\u{5D0}", " ", "\u{5D1}", [1], [0]),
+        ("\u{627}", "1", "", [2], [0]),
+        ("\u{202B}", "a", "", [2], [0]),
+        ("\u{2067}", "1", "\u{2069}", [2], [0]),
+    ];
+    for (prefix, text, suffix, levels, alone) in cases.iter() {
+        let resolved = state::resolve_levels_with_context(
+            &mut state,
+            prefix,
+            text,
+            suffix,
+            Granularity::Char,
+            Some(0),
+        );
+        assert_eq!(resolved.levels, levels);
+        let alone_levels = state::resolve_levels(&mut state, text, Granularity::Char, Some(0));
+        assert_eq!(alone_levels.levels, alone);
+    }
+    let resolved = state::resolve_levels_with_context(
+        &mut state,
+        "\u{5D0} ",
+        "\u{5D1}12",
+        " abc",
+        Granularity::Byte,
+        None,
+    );
+    assert_eq!(resolved.base_level, 1);
+    assert_eq!(resolved.levels, [1, 1, 2, 2]);
+}
+
+#[test]
+fn embeddings() {
+    fn node(
+        kind: state::EmbeddingKind,
+        direction: Type,
+        level: Level,
+        (start, end, closed_implicitly): (usize, usize, bool),
+        children: Vec<state::Embedding>,
+    ) -> state::Embedding {
+        state::Embedding {
+            kind,
+            direction,
+            level,
+            start,
+            end,
+            closed_implicitly,
+            children,
+        }
+    }
+    use state::EmbeddingKind::*;
+    let mut state = State::new();
+    let text = "a\u{202B}b\u{2068}\u{5D0}\u{202A}c\u{2069}d\u{202C}\u{2066}";
+    resolve(&mut state, text, Some(0));
+    let lre = node(Embedding, Type::L, 4, (5, 7, true), vec![]);
+    let fsi = node(Isolate, Type::R, 3, (3, 7, false), vec![lre]);
+    let rle = node(Embedding, Type::R, 1, (1, 9, false), vec![fsi]);
+    let lri = node(Isolate, Type::L, 2, (10, 11, true), vec![]);
+    assert_eq!(state.embeddings(), [rle, lri]);
+    // Unmatched closing characters and overflowing initiators are ignored.
+    let text = "\u{2069}\u{202C}".to_string() + &"\u{202D}".repeat(70);
+    resolve(&mut state, &text, Some(0));
+    let mut depth = 0;
+    let mut nodes = state.embeddings();
+    while let Some(node) = nodes.pop() {
+        assert_eq!(node.start, depth + 2);
+        depth += 1;
+        nodes = node.children;
+    }
+    assert_eq!(depth, 62);
+}
+
+#[test]
+fn resolve_with_diagnostics() {
+    use state::{Diagnostic, DiagnosticKind::*};
+    let diagnostics = |text: &str| {
+        let mut state = State::new();
+        let mut resolved = Resolved::default();
+        let mut diagnostics = Vec::new();
+        state.resolve_with_diagnostics(
+            text.chars().map(|ch| (ch, Type::from_char(ch))),
+            Some(0),
+            &mut resolved,
+            &mut |d: Diagnostic| diagnostics.push((d.kind, d.index)),
+        );
+        diagnostics
+    };
+    assert_eq!(
+        diagnostics("\u{2069}a\u{202C}\u{2067}b"),
+        [(UnmatchedPdi, 0), (UnmatchedPdf, 2), (UnclosedIsolate, 3)]
+    );
+    assert_eq!(
+        diagnostics("\u{2067}\u{202B}\u{2069}\u{202C}"),
+        [(UnmatchedPdf, 3)]
+    );
+    let text = "\u{202D}".repeat(64) + &"\u{202C}".repeat(64);
+    assert_eq!(diagnostics(&text), [(DepthExceeded, 62)]);
+    let text = "\u{5D0}".to_string() + &"(".repeat(64) + ")";
+    assert_eq!(diagnostics(&text), [(BracketStackOverflow, 64)]);
+    assert_eq!(diagnostics("a \u{5D0} (\u{2066}b\u{2069})"), []);
+}
+
+#[test]
+fn accessors_after_str_entry_points() {
+    type Accessors = (Vec<SequenceInfo>, Vec<Type>, Vec<bool>);
+    let accessors = |state: &State| -> Accessors {
+        (
+            sequences(state),
+            state.resolved_types().collect(),
+            state.removed().collect(),
+        )
+    };
+    // Text that does not require bidirectional processing is not resolved,
+    // so the accessors describe an empty paragraph.
+    let empty = (Vec::new(), Vec::new(), Vec::new());
+    for (text, simple) in [
+        ("abc", true),
+        ("a\u{200B}b 1", true),
+        ("a\nb", true),
+        ("", true),
+        ("a\u{5D0}b", false),
+    ]
+    .iter()
+    .copied()
+    {
+        let mut state = State::new();
+        resolve(&mut state, text, None);
+        let expected = if simple {
+            empty.clone()
+        } else {
+            accessors(&state)
+        };
+        let mut state = State::new();
+        state::resolve_levels(&mut state, text, Granularity::Byte, None);
+        assert_eq!(accessors(&state), expected, "{:?}", text);
+        let mut resolved = Resolved::default();
+        let mut state = State::new();
+        state::resolve_levels_into(&mut state, text, Granularity::Char, None, &mut resolved);
+        assert_eq!(accessors(&state), expected, "{:?}", text);
+        let mut state = State::new();
+        state::resolve_ranges_with(&mut state, text, Granularity::Char, None, |_, _| {});
+        assert_eq!(accessors(&state), expected, "{:?}", text);
+    }
+}
@@ -0,0 +1,76 @@
+use bidi::state::State;
+use bidi::structured::{self, *};
+use bidi::*;
+
+fn visual(text: &str) -> String {
+    let mut state = State::new();
+    let resolved = structured::resolve_levels(&mut state, text, &FilePath, Some(1));
+    let mut out = String::new();
+    write_reordered(text, &resolved, WriteOptions::default(), &mut out).unwrap();
+    out
+}
+
+#[test]
+fn structured_tokens() {
+    let ranges = |text, syntax: &dyn Syntax| tokens(text, syntax).collect::<Vec<_>>();
+    assert_eq!(
+        ranges("c:\\dir\\a.txt", &FilePath),
+        [0..1, 3..6, 7..8, 9..12]
+    );
+    assert_eq!(
+        ranges("http://x.org/a?b=c", &Url),
+        [0..4, 7..8, 9..12, 13..14, 15..16, 17..18]
+    );
+    assert_eq!(
+        ranges("\"A B\" <a@b.c>", &Email),
+        [1..2, 3..4, 7..8, 9..10, 11..12]
+    );
+    assert_eq!(
+        ranges("name = 'it''s, ok' AND x>1", &Expression),
+        [0..4, 7..18, 19..22, 23..24, 25..26]
+    );
+    assert_eq!(ranges("a;b", &Separators::new(";")), [0..1, 2..3]);
+    assert_eq!(ranges("", &Url), []);
+}
+
+#[test]
+fn structured_process() {
+    assert_eq!(
+        process("a\\\u{5D0}", &FilePath),
+        "\u{2066}\u{2066}a\u{2069}\\\u{2066}\u{5D0}\u{2069}\u{2069}"
+    );
+    let syntax = Separators::new("/").with_direction(Type::R);
+    assert_eq!(process("/", &syntax), "\u{2067}/\u{2069}");
+}
+
+#[test]
+fn structured_resolve_levels() {
+    // Without isolates, the separator between the Hebrew folders would
+    // resolve to R and reverse their order.
+    let text = "c:\\\u{5D0}\u{5D1}\\\u{5D2}\u{5D3}\\f.txt";
+    assert_eq!(visual(text), "c:\\\u{5D1}\u{5D0}\\\u{5D3}\u{5D2}\\f.txt");
+    let mut state = State::new();
+    let resolved = structured::resolve_levels(&mut state, text, &FilePath, Some(1));
+    assert_eq!(resolved.levels.len(), text.chars().count());
+    assert_eq!(resolved.levels, [4, 2, 2, 5, 5, 2, 5, 5, 2, 4, 2, 4, 4, 4]);
+    // The character data of the state is used.
+    state.set_provider(Some(&Private));
+    let resolved = structured::resolve_levels(&mut state, "a/\u{E000}\u{E001}", &Url, Some(0));
+    let expected = structured::resolve_levels(&mut State::new(), "a/\u{5D0}\u{5D1}", &Url, Some(0));
+    assert_eq!(resolved.levels, expected.levels);
+}
+
+struct Private;
+
+impl Provider for Private {
+    fn bidi_class(&self, ch: char) -> Type {
+        match ch {
+            '\u{E000}'..='\u{F8FF}' => Type::R,
+            _ => BuiltinProvider.bidi_class(ch),
+        }
+    }
+
+    fn bracket_type(&self, ch: char) -> BracketType {
+        BuiltinProvider.bracket_type(ch)
+    }
+}
@@ -0,0 +1,41 @@
+use bidi::state::State;
+use bidi::*;
+
+#[test]
+fn trace_records_each_phase() {
+    let text = "\u{5D0} (a) 1";
+    let mut state = State::new();
+    let mut resolved = Resolved::default();
+    let mut trace = String::new();
+    state
+        .resolve_traced(
+            text.chars().map(|ch| (ch, Type::from_char(ch))),
+            None,
+            &mut resolved,
+            &mut trace,
+        )
+        .unwrap();
+    for rules in &[
+        "[P1]",
+        "[P2, P3]",
+        "[X1-X8]",
+        "[BD13, X10]",
+        "[W1-W4]",
+        "[W5]",
+        "[W6, W7]",
+        "[N0]",
+        "[N1, N2]",
+        "[I1, I2]",
+        "[L1]",
+    ] {
+        assert!(trace.contains(rules), "missing phase {}", rules);
+    }
+    assert!(trace.contains("Sequence 0: level=1 sos=R eos=R"));
+    let mut untraced = Resolved::default();
+    state.resolve(
+        text.chars().map(|ch| (ch, Type::from_char(ch))),
+        None,
+        &mut untraced,
+    );
+    assert_eq!(resolved.levels, untraced.levels);
+}
@@ -0,0 +1,163 @@
+use bidi::*;
+
+fn visual(text: &str, options: WriteOptions) -> String {
+    let resolved = resolve_levels(text, Granularity::Char, Some(0));
+    let mut out = String::new();
+    write_reordered(text, &resolved, options, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn write_visual_order() {
+    let text = "ab \u{5D0}\u{5D1}(\u{5D2}) cd";
+    assert_eq!(
+        visual(text, WriteOptions::default()),
+        "ab )\u{5D2}(\u{5D1}\u{5D0} cd"
+    );
+    let options = WriteOptions {
+        mirror: true,
+        ..Default::default()
+    };
+    assert_eq!(visual(text, options), "ab (\u{5D2})\u{5D1}\u{5D0} cd");
+    let resolved = resolve_levels(text, Granularity::Byte, Some(0));
+    let mut out = String::new();
+    write_reordered(text, &resolved, options, &mut out).unwrap();
+    assert_eq!(out, "ab (\u{5D2})\u{5D1}\u{5D0} cd");
+    // Levels resolved for other text are rejected.
+    let resolved = resolve_levels("ab", Granularity::Char, Some(0));
+    let mut out = String::new();
+    assert!(write_reordered(text, &resolved, options, &mut out).is_err());
+    assert_eq!(out, "");
+}
+
+#[test]
+fn write_visual_order_options() {
+    let text = "a\u{202B}\u{5D0}\u{5B4}\u{5D1}\u{202C}\u{200F}";
+    assert_eq!(
+        visual(text, WriteOptions::default()),
+        "a\u{202B}\u{200F}\u{202C}\u{5D1}\u{5B4}\u{5D0}"
+    );
+    let options = WriteOptions {
+        remove_controls: true,
+        keep_combining: true,
+        ..Default::default()
+    };
+    assert_eq!(visual(text, options), "a\u{5D1}\u{5D0}\u{5B4}");
+}
+
+#[test]
+fn write_visual_order_round_trip() {
+    let marks = WriteOptions {
+        insert_marks: true,
+        ..Default::default()
+    };
+    let remove = WriteOptions {
+        remove_controls: true,
+        ..Default::default()
+    };
+    for (text, base_level) in &[
+        ("abc \u{5D0}\u{5D1} def", 0),
+        ("\u{5D0} 12", 0),
+        ("\u{5D0} 12 \u{5D1}", 0),
+        ("\u{627}\u{644} 3.5 - x", 0),
+        ("\u{661}\u{662} \u{663}", 0),
+        ("a \u{5D0}\u{5D1} 12 c", 1),
+        ("\u{5D0}, \u{5D1}!", 1),
+    ] {
+        let resolved = resolve_levels(text, Granularity::Char, Some(*base_level));
+        let mut expected = String::new();
+        write_reordered(text, &resolved, WriteOptions::default(), &mut expected).unwrap();
+        let mut visual = String::new();
+        write_reordered(text, &resolved, marks, &mut visual).unwrap();
+        assert!(!visual.contains("\u{200E}\u{200E}"));
+        // The visual text displays in the same order.
+        let resolved = resolve_levels(&visual, Granularity::Char, Some(0));
+        let mut output = String::new();
+        write_reordered(&visual, &resolved, remove, &mut output).unwrap();
+        assert_eq!(output, expected);
+    }
+    assert_eq!(
+        visual("abc \u{5D0}\u{5D1} def", marks),
+        "abc \u{5D1}\u{200E}\u{5D0} def"
+    );
+    assert_eq!(visual("\u{5D0} 12", marks), "12 \u{5D0}");
+    assert_eq!(
+        visual("\u{5D0} 12 \u{5D1}", marks),
+        "\u{5D1} \u{200E}12 \u{5D0}"
+    );
+}
+
+#[test]
+fn write_logical_order() {
+    let mut logical = String::new();
+    write_logical(
+        "\u{5D1}\u{5D0} abc",
+        Some(0),
+        InverseMode::LikeDirect,
+        &mut logical,
+    )
+    .unwrap();
+    assert_eq!(logical, "\u{5D0}\u{5D1}\u{200E} abc");
+    let remove = WriteOptions {
+        remove_controls: true,
+        ..Default::default()
+    };
+    for mode in &[InverseMode::LikeDirect, InverseMode::NumbersAsL] {
+        for (visual, base_level) in &[
+            ("\u{5D1}\u{5D0} abc", 0),
+            ("12 \u{5D2}\u{5D1}\u{5D0}", 0),
+            ("x \u{5D1}\u{5D0} 3.5 \u{5D2}", 0),
+            ("\u{661}\u{662}, a \u{5D0}!", 1),
+        ] {
+            let mut logical = String::new();
+            write_logical(visual, Some(*base_level), *mode, &mut logical).unwrap();
+            assert!(!logical.contains("\u{200E}\u{200E}"));
+            assert!(!logical.contains("\u{200F}\u{200F}"));
+            let resolved = resolve_levels(&logical, Granularity::Char, Some(*base_level));
+            let mut output = String::new();
+            write_reordered(&logical, &resolved, remove, &mut output).unwrap();
+            assert_eq!(&output, visual);
+        }
+    }
+}
+
+#[test]
+fn write_with_state_data() {
+    use bidi::state::{self, State};
+    let mut state = State::new();
+    state.set_provider(Some(&Private));
+    let text = "a \u{E000}\u{E001}";
+    let resolved = state::resolve_levels(&mut state, text, Granularity::Char, Some(0));
+    let options = WriteOptions {
+        insert_marks: true,
+        ..Default::default()
+    };
+    let mut out = String::new();
+    state::write_reordered(&state, text, &resolved, options, &mut out).unwrap();
+    assert_eq!(out, "a \u{E001}\u{200E}\u{E000}");
+    let mut logical = String::new();
+    state::write_logical(
+        &mut state,
+        "\u{E001}\u{E000} abc",
+        Some(0),
+        InverseMode::LikeDirect,
+        &mut logical,
+    )
+    .unwrap();
+    assert_eq!(logical, "\u{E000}\u{E001}\u{200E} abc");
+}
+
+struct Private;
+
+impl Provider for Private {
+    fn bidi_class(&self, ch: char) -> Type {
+        match ch {
+            '\u{E000}'..='\u{F8FF}' => Type::R,
+            _ => BuiltinProvider.bidi_class(ch),
+        }
+    }
+
+    fn bracket_type(&self, ch: char) -> BracketType {
+        BuiltinProvider.bracket_type(ch)
+    }
+}